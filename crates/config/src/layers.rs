@@ -0,0 +1,285 @@
+use std::path::PathBuf;
+
+use hocon::HoconLoader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Source};
+
+// A configuration read from several places, each one overriding the ones before:
+// 1. the defaults of the type
+// 2. the system file, in `/etc/<app>/` on Linux, `/Library/Application Support/<app>/` on macOS
+//    and `%PROGRAMDATA%\<app>\` on Windows
+// 3. the file in the current directory, where files were read from before there were layers, so
+//    that they keep working, and handy when running from a checkout
+// 4. the user file, in `$XDG_CONFIG_HOME/<app>/` (`~/.config/<app>/`) on Linux,
+//    `~/Library/Application Support/<app>/` on macOS and `%APPDATA%\<app>\` on Windows
+// 5. an explicit file, that must exist
+// 6. environment variables named `<APP>_<KEY>`, with `__` between nested keys, like
+//    `WOTM_FLEET_CHANCE=0.3` or `WOTM_BINDINGS__SEND=[{Key: S}]`
+// Missing files are skipped. Environment variables for keys the type doesn't have are ignored, so
+// the same ones can be used for several types.
+pub struct Layers {
+    app: String,
+    file: String,
+    explicit: Option<String>,
+    // the ones of the platform and of the process when not set
+    user_dir: Option<PathBuf>,
+    env: Option<Vec<(String, String)>>,
+}
+
+impl Layers {
+    pub fn new(app: &str, file: &str) -> Layers {
+        Layers {
+            app: app.to_string(),
+            file: file.to_string(),
+            explicit: None,
+            user_dir: None,
+            env: None,
+        }
+    }
+
+    pub fn with_explicit(mut self, path: Option<String>) -> Layers {
+        self.explicit = path;
+        self
+    }
+
+    // directory the user file is found in, under `<app>/`
+    pub fn with_user_dir(mut self, dir: PathBuf) -> Layers {
+        self.user_dir = Some(dir);
+        self
+    }
+
+    // environment variables to read instead of the ones of the process
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Layers {
+        self.env = Some(vars.into_iter().collect());
+        self
+    }
+
+    // files that are read, lowest precedence first
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.optional_files();
+        files.extend(self.explicit.iter().map(PathBuf::from));
+        files
+    }
+
+    fn optional_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![];
+        if let Some(dir) = system_dir() {
+            files.push(dir.join(&self.app).join(&self.file));
+        }
+        files.push(PathBuf::from(&self.file));
+        if let Some(dir) = self.user_dir() {
+            files.push(dir.join(&self.app).join(&self.file));
+        }
+        files
+    }
+
+    // where changes made by the user should be written, it takes precedence over the system file
+    // and the current directory
+    pub fn user_file(&self) -> PathBuf {
+        match self.user_dir() {
+            Some(dir) => dir.join(&self.app).join(&self.file),
+            None => PathBuf::from(&self.file),
+        }
+    }
+
+    fn user_dir(&self) -> Option<PathBuf> {
+        self.user_dir.clone().or_else(user_dir)
+    }
+
+    pub fn read<'a, T>(&self) -> Result<T, Error>
+    where
+        T: Default + Serialize + Deserialize<'a>,
+    {
+        let mut sources = self
+            .optional_files()
+            .into_iter()
+            .filter(|file| file.exists())
+            .map(|file| Source::File(file.to_string_lossy().to_string()))
+            .collect::<Vec<_>>();
+        sources.extend(self.explicit.iter().cloned().map(Source::File));
+        sources.push(Source::Env {
+            prefix: format!("{}_", self.app.to_uppercase()),
+            vars: self
+                .env
+                .clone()
+                .unwrap_or_else(|| std::env::vars().collect()),
+        });
+        crate::read_sources(&sources)
+    }
+
+    // Like `read`, but when a layer can't be read the defaults are used, along with the error for
+    // the caller to report instead of stopping.
+    pub fn read_or_default<'a, T>(&self) -> (T, Option<Error>)
+    where
+        T: Default + Serialize + Deserialize<'a>,
+    {
+        match self.read() {
+            Ok(value) => (value, None),
+            Err(err) => (T::default(), Some(err)),
+        }
+    }
+}
+
+fn system_dir() -> Option<PathBuf> {
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(target_os = "windows") {
+        std::env::var_os("PROGRAMDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support"))
+    } else {
+        Some(PathBuf::from("/etc"))
+    }
+}
+
+fn user_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.map(|home| home.join(".config")))
+    }
+}
+
+// HOCON setting the keys of `default` that have a variable starting with `prefix`
+pub(crate) fn environment(prefix: &str, default: &Value, vars: &[(String, String)]) -> String {
+    let mut variables = vars
+        .iter()
+        .filter_map(|(name, value)| {
+            let keys = find_keys(default, name.strip_prefix(prefix)?)?;
+            Some((keys, value.clone()))
+        })
+        .collect::<Vec<_>>();
+    // the same order every time, whatever the order of the environment
+    variables.sort();
+    variables
+        .into_iter()
+        .map(|(keys, value)| {
+            let path = keys
+                .iter()
+                .map(|key| Value::String(key.clone()).to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+            let line = format!("{} = {}", path, value);
+            // values that aren't valid HOCON are taken as strings
+            if HoconLoader::new().load_str(&line).is_ok() {
+                line
+            } else {
+                format!("{} = {}", path, Value::String(value))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// keys of `default` matching `name` split on `__`, ignoring case
+fn find_keys(default: &Value, name: &str) -> Option<Vec<String>> {
+    let mut current = default;
+    let mut keys = vec![];
+    for part in name.split("__") {
+        let (key, value) = current
+            .as_object()?
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(part))?;
+        keys.push(key.clone());
+        current = value;
+    }
+    Some(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{Example, TempDir};
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn finds_keys_from_variable_names() {
+        let default = json!({ "fleet_chance": 0.5, "bindings": { "Send": [] } });
+        assert_eq!(
+            find_keys(&default, "FLEET_CHANCE"),
+            Some(vec!["fleet_chance".to_string()])
+        );
+        assert_eq!(
+            find_keys(&default, "BINDINGS__SEND"),
+            Some(vec!["bindings".to_string(), "Send".to_string()])
+        );
+        assert_eq!(find_keys(&default, "UNKNOWN"), None);
+        assert_eq!(find_keys(&default, "FLEET_CHANCE__MORE"), None);
+    }
+
+    #[test]
+    fn environment_to_hocon() {
+        let vars = vars(&[
+            ("CONFIGENVTEST_SPEED", "2.5"),
+            ("CONFIGENVTEST_NESTED__COUNT", "4"),
+            ("CONFIGENVTEST_NAME", "not hocon {"),
+            ("CONFIGENVTEST_UNKNOWN", "1"),
+            ("OTHER_SPEED", "3"),
+        ]);
+        let default = serde_json::to_value(Example::default()).unwrap();
+        assert_eq!(
+            environment("CONFIGENVTEST_", &default, &vars),
+            "\"name\" = \"not hocon {\"\n\"nested\".\"count\" = 4\n\"speed\" = 2.5"
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let dir = TempDir::new("precedence");
+        let explicit = dir.file("explicit.conf", "speed = 2.5\nnested { count = 2 }\n");
+        let layers = Layers::new("configlayerstest", "precedence.conf")
+            .with_explicit(Some(explicit.clone()))
+            .with_user_dir(dir.path().join("user"))
+            .with_env(vars(&[("CONFIGLAYERSTEST_SPEED", "3.5")]));
+        let user_file = layers.user_file();
+        assert!(user_file.starts_with(dir.path()));
+        std::fs::create_dir_all(user_file.parent().unwrap()).unwrap();
+        std::fs::write(
+            &user_file,
+            "speed = 1.5\nname = \"user\"\nnested { count = 1 }\n",
+        )
+        .unwrap();
+
+        let files = layers.files();
+        assert_eq!(files.last(), Some(&PathBuf::from(&explicit)));
+        assert_eq!(files[files.len() - 2], user_file);
+
+        let example = layers.read::<Example>().unwrap();
+        // the environment beats the explicit file
+        assert_eq!(example.speed, 3.5);
+        // the explicit file beats the user file
+        assert_eq!(example.nested.count, 2);
+        // the user file beats the defaults
+        assert_eq!(example.name, "user");
+        assert!(example.nested.enabled);
+    }
+
+    #[test]
+    fn explicit_file_must_exist() {
+        let dir = TempDir::new("explicit_file_must_exist");
+        let layers = Layers::new("configlayerstest", "explicit.conf")
+            .with_explicit(Some("config-test-missing-explicit.conf".to_string()))
+            .with_user_dir(dir.path().to_path_buf())
+            .with_env(vec![]);
+        match layers.read::<Example>() {
+            Err(Error::NotFound { path }) => assert_eq!(path, "config-test-missing-explicit.conf"),
+            other => panic!("expected not found, got {:?}", other),
+        }
+    }
+}
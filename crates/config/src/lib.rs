@@ -1,22 +1,554 @@
 use std::path::Path;
 
-use hocon::HoconLoader;
+use hocon::{Hocon, HoconLoader};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-pub fn read_from<'a, T>(path: &str) -> Result<T, Box<dyn std::error::Error>>
+mod layers;
+pub use layers::Layers;
+
+// how errors in environment variables name their source
+const ENVIRONMENT: &str = "environment";
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound {
+        path: String,
+    },
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    // the file is not valid HOCON, `line` starts at 1
+    Parse {
+        path: String,
+        line: usize,
+    },
+    // `key` is the path to the value, like `bindings.Send`
+    TypeMismatch {
+        path: String,
+        key: String,
+        expected: String,
+    },
+    // errors from the HOCON loader that can't be pinned to a key, like a failed include
+    Invalid {
+        path: String,
+        message: String,
+    },
+    Serialize(serde_json::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound { path } => write!(f, "{} not found", path),
+            Error::Io { path, source } => write!(f, "could not access {}: {}", path, source),
+            Error::Parse { path, line } => write!(
+                f,
+                "{}:{}: could not parse this line, check for a missing `=`, quote or bracket",
+                path, line
+            ),
+            Error::TypeMismatch {
+                path,
+                key,
+                expected,
+            } => write!(f, "{}: `{}` should be {}", path, key, expected),
+            Error::Invalid { path, message } => write!(f, "{}: {}", path, message),
+            Error::Serialize(err) => write!(f, "could not serialize: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Serialize(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    fn io(path: &str, source: std::io::Error) -> Error {
+        if source.kind() == std::io::ErrorKind::NotFound {
+            Error::NotFound {
+                path: path.to_string(),
+            }
+        } else {
+            Error::Io {
+                path: path.to_string(),
+                source,
+            }
+        }
+    }
+}
+
+// Reads `path` over the defaults of `T`, a missing file gives the defaults.
+pub fn read_from<'a, T>(path: &str) -> Result<T, Error>
 where
     T: Default + Serialize + Deserialize<'a>,
 {
-    let default = T::default();
-
     if Path::new(path).exists() {
-        let default_as_json = serde_json::to_string(&default)?;
-        let parsed: T = HoconLoader::new()
-            .load_str(&default_as_json)?
-            .load_file(path)?
-            .resolve()?;
-        Ok(parsed)
+        read_file(path)
     } else {
-        Ok(default)
+        Ok(T::default())
+    }
+}
+
+// Like `read_from`, but the file must exist.
+pub fn read_file<'a, T>(path: &str) -> Result<T, Error>
+where
+    T: Default + Serialize + Deserialize<'a>,
+{
+    read_sources(&[Source::File(path.to_string())])
+}
+
+pub(crate) enum Source {
+    // must exist
+    File(String),
+    // the variables starting with `prefix`
+    Env {
+        prefix: String,
+        vars: Vec<(String, String)>,
+    },
+}
+
+// Applies each source in order over the defaults of `T`. Errors name the source they come from.
+pub(crate) fn read_sources<'a, T>(sources: &[Source]) -> Result<T, Error>
+where
+    T: Default + Serialize + Deserialize<'a>,
+{
+    let default_as_json = serde_json::to_value(T::default()).map_err(Error::Serialize)?;
+    let invalid = |label: &str, err: hocon::Error| Error::Invalid {
+        path: label.to_string(),
+        message: err.to_string(),
+    };
+
+    let mut loader = HoconLoader::new()
+        .load_str(&default_as_json.to_string())
+        .map_err(|err| invalid("defaults", err))?;
+    // what each source sets, to find the one responsible for a wrong value
+    let mut layers = vec![];
+    for source in sources {
+        let (label, content) = match source {
+            Source::File(path) => (
+                path.clone(),
+                std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?,
+            ),
+            Source::Env { prefix, vars } => (
+                ENVIRONMENT.to_string(),
+                layers::environment(prefix, &default_as_json, vars),
+            ),
+        };
+        loader = match source {
+            Source::File(path) => loader.load_file(path),
+            Source::Env { .. } => loader.load_str(&content),
+        }
+        .map_err(|err| match err {
+            hocon::Error::Parse => Error::Parse {
+                path: label.clone(),
+                line: locate_parse_error(&content),
+            },
+            err => invalid(&label, err),
+        })?;
+        let values = HoconLoader::new()
+            .load_str(&content)
+            .and_then(|loader| loader.hocon())
+            .map(hocon_to_json)
+            .unwrap_or(Value::Null);
+        layers.push((label, values));
+    }
+
+    let hocon = loader.hocon().map_err(|err| {
+        invalid(
+            layers
+                .last()
+                .map(|(label, _)| label.as_str())
+                .unwrap_or("defaults"),
+            err,
+        )
+    })?;
+    hocon.clone().resolve().map_err(|err| {
+        match locate_type_mismatch::<T>(&default_as_json, &hocon_to_json(hocon)) {
+            Some((keys, expected)) => Error::TypeMismatch {
+                path: layers
+                    .iter()
+                    .rev()
+                    .find(|(_, values)| get(values, &keys).is_some())
+                    .map(|(label, _)| label.clone())
+                    .unwrap_or_default(),
+                key: keys.join("."),
+                expected,
+            },
+            None => invalid(
+                layers
+                    .last()
+                    .map(|(label, _)| label.as_str())
+                    .unwrap_or("defaults"),
+                err,
+            ),
+        }
+    })
+}
+
+// Like `read_from`, but a file that can't be read gives the defaults along with the error, for
+// the caller to report instead of stopping.
+pub fn read_or_default<'a, T>(path: &str) -> (T, Option<Error>)
+where
+    T: Default + Serialize + Deserialize<'a>,
+{
+    match read_from(path) {
+        Ok(value) => (value, None),
+        Err(err) => (T::default(), Some(err)),
+    }
+}
+
+// The HOCON parser doesn't say where it failed. Longer and longer beginnings of the file are
+// parsed, with their open brackets closed, and the first line that breaks them is the culprit.
+// Where in the line isn't known.
+fn locate_parse_error(content: &str) -> usize {
+    let lines = content.lines().collect::<Vec<_>>();
+    for end in 1..=lines.len() {
+        let mut beginning = lines[..end].join("\n");
+        beginning.push('\n');
+        beginning.push_str(&closing_brackets(&beginning));
+        if HoconLoader::new().load_str(&beginning).is_err() {
+            return end;
+        }
+    }
+    lines.len().max(1)
+}
+
+fn closing_brackets(text: &str) -> String {
+    let mut open = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' | '[' if !in_string => open.push(c),
+            '}' | ']' if !in_string => {
+                open.pop();
+            }
+            _ => (),
+        }
+    }
+    open.iter()
+        .rev()
+        .map(|c| if *c == '{' { '}' } else { ']' })
+        .collect()
+}
+
+// Values of the file are put one at a time over the defaults until one breaks `T`. When it is an
+// object that has defaults, its own values are tried the same way to find the innermost key.
+fn locate_type_mismatch<'a, T>(default: &Value, file: &Value) -> Option<(Vec<String>, String)>
+where
+    T: Deserialize<'a>,
+{
+    let breaks = |keys: &[String], value: &Value| {
+        let mut candidate = default.clone();
+        set(&mut candidate, keys, value.clone());
+        T::deserialize(candidate).err()
+    };
+    let mut keys = vec![];
+    let mut error = None;
+    while get(default, &keys).map(Value::is_object).unwrap_or(false) {
+        let fields = match get(file, &keys) {
+            Some(Value::Object(fields)) => fields,
+            _ => break,
+        };
+        let found = fields.iter().find_map(|(key, value)| {
+            let mut keys = keys.clone();
+            keys.push(key.clone());
+            breaks(&keys, value).map(|err| (keys, err))
+        });
+        match found {
+            Some((found_keys, err)) => {
+                keys = found_keys;
+                error = Some(err);
+            }
+            None => break,
+        }
+    }
+    error.map(|err| (keys, expected_type(&err.to_string())))
+}
+
+// serde messages look like "invalid type: string \"a\", expected f32 at line 1 column 2"
+fn expected_type(message: &str) -> String {
+    message
+        .split("expected ")
+        .nth(1)
+        .map(|expected| {
+            expected
+                .split(" at line ")
+                .next()
+                .unwrap_or(expected)
+                .to_string()
+        })
+        .unwrap_or_else(|| message.to_string())
+}
+
+fn get<'v>(value: &'v Value, keys: &[String]) -> Option<&'v Value> {
+    keys.iter()
+        .try_fold(value, |value, key| value.as_object()?.get(key))
+}
+
+fn set(value: &mut Value, keys: &[String], new: Value) {
+    let mut value = value;
+    for key in keys {
+        if !value.is_object() {
+            *value = Value::Object(Default::default());
+        }
+        value = value
+            .as_object_mut()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert(Value::Null);
+    }
+    *value = new;
+}
+
+// Writes `value` to `path` as indented JSON, which is valid HOCON and can be read back with
+// `read_from`. Keys already in the file that `T` doesn't have are kept, so a file shared with
+// another version of the program, or edited by hand, doesn't lose them. Missing directories are
+// created.
+pub fn write_to<T>(path: &str, value: &T) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    let mut written = serde_json::to_value(value).map_err(Error::Serialize)?;
+    if Path::new(path).exists() {
+        let existing = HoconLoader::new()
+            .load_file(path)
+            .and_then(|loader| loader.hocon())
+            .map_err(|err| Error::Invalid {
+                path: path.to_string(),
+                message: err.to_string(),
+            })?;
+        keep_unknown_keys(&mut written, hocon_to_json(existing));
+    }
+    let mut content = serde_json::to_string_pretty(&written).map_err(Error::Serialize)?;
+    content.push('\n');
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir).map_err(|err| Error::io(path, err))?;
+    }
+    std::fs::write(path, content).map_err(|err| Error::io(path, err))?;
+    Ok(())
+}
+
+fn keep_unknown_keys(value: &mut Value, existing: Value) {
+    if let (Value::Object(value), Value::Object(existing)) = (value, existing) {
+        for (key, existing) in existing {
+            match value.get_mut(&key) {
+                Some(value) => keep_unknown_keys(value, existing),
+                None => {
+                    value.insert(key, existing);
+                }
+            }
+        }
+    }
+}
+
+fn hocon_to_json(hocon: Hocon) -> Value {
+    match hocon {
+        Hocon::Real(real) => serde_json::Number::from_f64(real)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        Hocon::Integer(integer) => Value::Number(integer.into()),
+        Hocon::String(string) => Value::String(string),
+        Hocon::Boolean(boolean) => Value::Bool(boolean),
+        Hocon::Array(array) => Value::Array(array.into_iter().map(hocon_to_json).collect()),
+        Hocon::Hash(hash) => Value::Object(
+            hash.into_iter()
+                .map(|(key, value)| (key, hocon_to_json(value)))
+                .collect(),
+        ),
+        Hocon::Null | Hocon::BadValue(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub(crate) struct Example {
+        pub speed: f32,
+        pub name: String,
+        pub nested: Nested,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub(crate) struct Nested {
+        pub count: u32,
+        pub enabled: bool,
+    }
+
+    impl Default for Example {
+        fn default() -> Self {
+            Example {
+                speed: 1.,
+                name: "default".to_string(),
+                nested: Nested {
+                    count: 0,
+                    enabled: true,
+                },
+            }
+        }
+    }
+
+    // a directory of the temporary directory for a single test, removed once the test is done
+    pub(crate) struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(test: &str) -> TempDir {
+            let path =
+                std::env::temp_dir().join(format!("config-test-{}-{}", test, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        pub(crate) fn path(&self) -> &std::path::Path {
+            &self.0
+        }
+
+        pub(crate) fn file(&self, name: &str, content: &str) -> String {
+            let path = self.0.join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_keys() {
+        let mut value = json!({ "speed": 2.0, "nested": { "count": 3 } });
+        let existing = json!({
+            "speed": 1.0,
+            "old": "kept",
+            "nested": { "count": 1, "gone": true }
+        });
+        keep_unknown_keys(&mut value, existing);
+        assert_eq!(
+            value,
+            json!({
+                "speed": 2.0,
+                "old": "kept",
+                "nested": { "count": 3, "gone": true }
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip_with_unknown_key() {
+        let dir = TempDir::new("round_trip_with_unknown_key");
+        let path = dir.file("round_trip.conf", "speed = 3.5\nold_key = \"kept\"\n");
+        let mut example: Example = read_file(&path).unwrap();
+        assert_eq!(example.speed, 3.5);
+        assert_eq!(example.name, "default");
+
+        example.name = "changed".to_string();
+        write_to(&path, &example).unwrap();
+        assert_eq!(read_file::<Example>(&path).unwrap(), example);
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["old_key"], "kept");
+    }
+
+    #[test]
+    fn expected_type_from_serde_messages() {
+        assert_eq!(
+            expected_type("invalid type: string \"a\", expected f32 at line 1 column 2"),
+            "f32"
+        );
+        assert_eq!(
+            expected_type("invalid type: integer `1`, expected a boolean"),
+            "a boolean"
+        );
+        assert_eq!(
+            expected_type("missing field `speed`"),
+            "missing field `speed`"
+        );
+    }
+
+    #[test]
+    fn locates_parse_error_line() {
+        assert_eq!(locate_parse_error("speed = 1\n}\nname = \"a\"\n"), 2);
+        assert_eq!(
+            locate_parse_error("nested {\n  count = 2\n}\n}\nspeed = 1\n"),
+            4
+        );
+    }
+
+    #[test]
+    fn locates_type_mismatch_key() {
+        let default = serde_json::to_value(Example::default()).unwrap();
+        let file = json!({ "speed": 2.0, "nested": { "count": "many", "enabled": false } });
+        assert_eq!(
+            locate_type_mismatch::<Example>(&default, &file),
+            Some((
+                vec!["nested".to_string(), "count".to_string()],
+                "u32".to_string()
+            ))
+        );
+        let file = json!({ "speed": 2.0, "nested": { "count": 2 } });
+        assert_eq!(locate_type_mismatch::<Example>(&default, &file), None);
+    }
+
+    #[test]
+    fn typo_reports_line() {
+        let dir = TempDir::new("typo_reports_line");
+        let path = dir.file("typo_line.conf", "speed = 2.5\nname = \"b\"\n}\n");
+        match read_file::<Example>(&path) {
+            Err(Error::Parse {
+                path: error_path,
+                line,
+            }) => {
+                assert_eq!(error_path, path);
+                assert_eq!(line, 3);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn typo_reports_key_path() {
+        let dir = TempDir::new("typo_reports_key_path");
+        let path = dir.file("typo_key.conf", "speed = 2.5\nnested { count = many }\n");
+        match read_file::<Example>(&path) {
+            Err(Error::TypeMismatch {
+                path: error_path,
+                key,
+                expected,
+            }) => {
+                assert_eq!(error_path, path);
+                assert_eq!(key, "nested.count");
+                assert_eq!(expected, "u32");
+            }
+            other => panic!("expected a type mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        match read_file::<Example>("config-test-missing.conf") {
+            Err(Error::NotFound { path }) => assert_eq!(path, "config-test-missing.conf"),
+            other => panic!("expected not found, got {:?}", other),
+        }
+        assert_eq!(
+            read_from::<Example>("config-test-missing.conf").unwrap(),
+            Example::default()
+        );
     }
 }
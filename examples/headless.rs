@@ -0,0 +1,47 @@
+use bevy::{app::AppExit, prelude::*};
+
+// Matches without a player stop after this much match time.
+const TIME_LIMIT: f32 = 600.;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let frames = (TIME_LIMIT * 60.) as usize + 1;
+    let mut builder = App::build();
+    builder
+        .add_resource(wotm::GameScreen {
+            current_screen: wotm::Screen::Game,
+            highscore: 0,
+        })
+        // fixed frame times, the match runs as fast as it can
+        .add_resource(wotm::clock::GameClock::scripted(vec![1. / 60.; frames]));
+    if let Some(path) = std::env::args().nth(1) {
+        builder.add_resource(wotm::game::map::MapDefinition::load(&path)?);
+    }
+    builder
+        .add_plugins(MinimalPlugins)
+        .add_plugins(wotm::HeadlessPlugins)
+        .add_system(end_of_match)
+        .run();
+    Ok(())
+}
+
+fn end_of_match(
+    game: Res<wotm::game::Game>,
+    game_screen: Res<wotm::GameScreen>,
+    mut app_exit_events: ResMut<Events<AppExit>>,
+    moons: Query<&wotm::game::OwnedBy, With<wotm::game::Moon>>,
+) {
+    if game_screen.current_screen != wotm::Screen::Game || game.elapsed >= TIME_LIMIT {
+        println!(
+            "{:?} after {:.1}s with a score of {}",
+            game.state, game.elapsed, game.score as u32
+        );
+        let mut owners = moons.iter().cloned().collect::<Vec<_>>();
+        owners.sort_by_key(|owner| format!("{:?}", owner));
+        owners.dedup();
+        for owner in owners {
+            let held = moons.iter().filter(|moon| **moon == owner).count();
+            println!("{:?} holds {} moons", owner, held);
+        }
+        app_exit_events.send(AppExit);
+    }
+}
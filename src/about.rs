@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use tracing::info;
 
+use crate::input::Action;
+
 const CURRENT_SCREEN: crate::Screen = crate::Screen::About;
 
 struct ScreenTag;
@@ -378,15 +380,13 @@ fn tear_down(
 fn input_system(
     mut game_screen: ResMut<crate::GameScreen>,
     screen: Res<Screen>,
-    mouse_button_input: Res<Input<MouseButton>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<crate::input::Actions>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN
         && screen.loaded
-        && (mouse_button_input.just_pressed(MouseButton::Left)
-            || keyboard_input.just_released(KeyCode::Escape)
-            || keyboard_input.just_released(KeyCode::Space)
-            || keyboard_input.just_released(KeyCode::Return))
+        && (actions.just_pressed(Action::Select)
+            || actions.just_released(Action::Back)
+            || actions.just_released(Action::Confirm))
     {
         game_screen.current_screen = crate::Screen::Menu;
     }
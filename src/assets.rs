@@ -48,15 +48,16 @@ pub struct AssetHandles {
 
 #[derive(Clone)]
 pub struct GameHandles {
-    pub planets: Vec<(Handle<ColorMaterial>, u32)>,
+    pub planets: Vec<Handle<ColorMaterial>>,
     pub orbiters: Vec<Handle<ColorMaterial>>,
     pub meteors: Vec<Handle<ColorMaterial>>,
     pub ships: Vec<Vec<Handle<ColorMaterial>>>,
+    pub missiles: Vec<Handle<ColorMaterial>>,
     pub explosion_handle: Handle<TextureAtlas>,
 }
 
 impl AssetHandles {
-    pub fn get_planet_names(&self) -> Vec<&'static str> {
+    pub fn get_planet_names() -> Vec<&'static str> {
         include!("../assets/star_names.in").to_vec()
     }
 
@@ -268,26 +269,26 @@ impl AssetHandles {
         if self.game.is_none() {
             self.game = Some(GameHandles {
                 planets: vec![
-                    (colormaterial!(mats, assets, "planets/1.png"), 365),
-                    (colormaterial!(mats, assets, "planets/2.png"), 340),
-                    (colormaterial!(mats, assets, "planets/3.png"), 390),
-                    (colormaterial!(mats, assets, "planets/4.png"), 250),
-                    (colormaterial!(mats, assets, "planets/5.png"), 395),
-                    (colormaterial!(mats, assets, "planets/6.png"), 360),
-                    (colormaterial!(mats, assets, "planets/7.png"), 430),
-                    (colormaterial!(mats, assets, "planets/8.png"), 430),
-                    (colormaterial!(mats, assets, "planets/9.png"), 395),
-                    (colormaterial!(mats, assets, "planets/10.png"), 400),
-                    (colormaterial!(mats, assets, "planets/11.png"), 400),
-                    (colormaterial!(mats, assets, "planets/12.png"), 395),
-                    (colormaterial!(mats, assets, "planets/13.png"), 375),
-                    (colormaterial!(mats, assets, "planets/14.png"), 370),
-                    (colormaterial!(mats, assets, "planets/15.png"), 385),
-                    (colormaterial!(mats, assets, "planets/16.png"), 380),
-                    (colormaterial!(mats, assets, "planets/17.png"), 385),
-                    (colormaterial!(mats, assets, "planets/18.png"), 385),
-                    (colormaterial!(mats, assets, "planets/19.png"), 370),
-                    (colormaterial!(mats, assets, "planets/20.png"), 390),
+                    colormaterial!(mats, assets, "planets/1.png"),
+                    colormaterial!(mats, assets, "planets/2.png"),
+                    colormaterial!(mats, assets, "planets/3.png"),
+                    colormaterial!(mats, assets, "planets/4.png"),
+                    colormaterial!(mats, assets, "planets/5.png"),
+                    colormaterial!(mats, assets, "planets/6.png"),
+                    colormaterial!(mats, assets, "planets/7.png"),
+                    colormaterial!(mats, assets, "planets/8.png"),
+                    colormaterial!(mats, assets, "planets/9.png"),
+                    colormaterial!(mats, assets, "planets/10.png"),
+                    colormaterial!(mats, assets, "planets/11.png"),
+                    colormaterial!(mats, assets, "planets/12.png"),
+                    colormaterial!(mats, assets, "planets/13.png"),
+                    colormaterial!(mats, assets, "planets/14.png"),
+                    colormaterial!(mats, assets, "planets/15.png"),
+                    colormaterial!(mats, assets, "planets/16.png"),
+                    colormaterial!(mats, assets, "planets/17.png"),
+                    colormaterial!(mats, assets, "planets/18.png"),
+                    colormaterial!(mats, assets, "planets/19.png"),
+                    colormaterial!(mats, assets, "planets/20.png"),
                 ],
                 orbiters: vec![
                     colormaterial!(mats, assets, "Station/spaceStation_017.png"),
@@ -335,6 +336,15 @@ impl AssetHandles {
                         colormaterial!(mats, assets, "Ships/enemyBlack5.png"),
                     ],
                 ],
+                missiles: (1..=40)
+                    .map(|i| {
+                        colormaterial!(
+                            mats,
+                            assets,
+                            format!("Missiles/spaceMissiles_{:03}.png", i).as_str()
+                        )
+                    })
+                    .collect(),
                 explosion_handle: atlases.add(Self::build_explosion_atlas(
                     assets.load("spritesheet_regularExplosion.png"),
                 )),
@@ -0,0 +1,335 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::game::ai::Strategy;
+use crate::game::map::{FactionDefinition, MapDefinition, MoonDefinition, PlanetDefinition};
+use crate::game::{Game, GameState, Moon, OwnedBy};
+use crate::{Config, ConfigOverrides};
+
+pub const CAMPAIGN_FILE: &str = "campaign.conf";
+pub const PROGRESS_FILE: &str = "progress.conf";
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Campaign>()
+            .add_startup_system(load_campaign)
+            .add_system(win_condition)
+            .add_system(finish_level);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum WinCondition {
+    ConquerPlanet,
+    FreeAllMoons,
+    // seconds to hold at least one moon
+    Survive(f32),
+}
+
+impl Default for WinCondition {
+    fn default() -> Self {
+        WinCondition::ConquerPlanet
+    }
+}
+
+impl std::fmt::Display for WinCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinCondition::ConquerPlanet => write!(f, "conquer the planet"),
+            WinCondition::FreeAllMoons => write!(f, "free all moons"),
+            WinCondition::Survive(seconds) => write!(f, "survive for {}s", *seconds as u32),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Level {
+    pub name: String,
+    pub map: MapDefinition,
+    #[serde(default)]
+    pub config: ConfigOverrides,
+    #[serde(default)]
+    pub win: WinCondition,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Levels {
+    pub levels: Vec<Level>,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        let planet = PlanetDefinition {
+            x: -200.,
+            y: 0.,
+            kind: None,
+            name: None,
+            rotation: None,
+        };
+        Levels {
+            levels: vec![
+                Level {
+                    name: "First Light".to_string(),
+                    map: MapDefinition {
+                        planet: planet.clone(),
+                        moons: vec![
+                            MoonDefinition::orbiting_at(120.),
+                            MoonDefinition::orbiting_at(220.),
+                        ],
+                        player_start: Some(1),
+                        factions: vec![],
+                        planet_strategy: None,
+                    },
+                    config: ConfigOverrides {
+                        fleet_chance: Some(0.2),
+                        asteroid_chance: Some(0.),
+                        ..Default::default()
+                    },
+                    win: WinCondition::FreeAllMoons,
+                },
+                Level {
+                    name: "Siege".to_string(),
+                    map: MapDefinition {
+                        planet: planet.clone(),
+                        moons: vec![
+                            MoonDefinition::orbiting_at(100.),
+                            MoonDefinition::orbiting_at(200.),
+                            MoonDefinition::orbiting_at(300.),
+                        ],
+                        player_start: Some(3),
+                        factions: vec![],
+                        planet_strategy: None,
+                    },
+                    config: ConfigOverrides {
+                        fleet_chance: Some(0.3),
+                        ..Default::default()
+                    },
+                    win: WinCondition::ConquerPlanet,
+                },
+                Level {
+                    name: "Hold the Line".to_string(),
+                    map: MapDefinition {
+                        planet: planet.clone(),
+                        moons: vec![
+                            MoonDefinition::orbiting_at(110.),
+                            MoonDefinition::orbiting_at(190.),
+                            MoonDefinition::orbiting_at(270.),
+                        ],
+                        player_start: Some(1),
+                        factions: vec![],
+                        planet_strategy: None,
+                    },
+                    config: ConfigOverrides {
+                        fleet_delay: Some(2.),
+                        fleet_chance: Some(0.6),
+                        ..Default::default()
+                    },
+                    win: WinCondition::Survive(180.),
+                },
+                Level {
+                    name: "Three Way".to_string(),
+                    map: MapDefinition {
+                        planet: planet.clone(),
+                        moons: vec![
+                            MoonDefinition::orbiting_at(100.),
+                            MoonDefinition::orbiting_at(160.),
+                            MoonDefinition {
+                                owner: Some(OwnedBy::Player(1)),
+                                ..MoonDefinition::orbiting_at(220.)
+                            },
+                            MoonDefinition::orbiting_at(280.),
+                            MoonDefinition {
+                                owner: Some(OwnedBy::Player(2)),
+                                ..MoonDefinition::orbiting_at(340.)
+                            },
+                        ],
+                        player_start: Some(1),
+                        factions: vec![
+                            FactionDefinition {
+                                player: 1,
+                                strategy: Strategy::Rush,
+                            },
+                            FactionDefinition {
+                                player: 2,
+                                strategy: Strategy::Turtle,
+                            },
+                        ],
+                        planet_strategy: None,
+                    },
+                    config: ConfigOverrides::default(),
+                    win: WinCondition::ConquerPlanet,
+                },
+                Level {
+                    name: "Crowded Orbit".to_string(),
+                    map: MapDefinition {
+                        planet,
+                        moons: (0..5)
+                            .map(|i| MoonDefinition::orbiting_at(90. + i as f32 * 55.))
+                            .collect(),
+                        player_start: None,
+                        factions: vec![],
+                        planet_strategy: None,
+                    },
+                    config: ConfigOverrides {
+                        asteroid_timer: Some(15.),
+                        ..Default::default()
+                    },
+                    win: WinCondition::ConquerPlanet,
+                },
+            ],
+        }
+    }
+}
+
+impl Levels {
+    fn check(&self) -> Result<(), String> {
+        if self.levels.is_empty() {
+            return Err("a campaign needs at least one level".to_string());
+        }
+        for level in self.levels.iter() {
+            level
+                .map
+                .check()
+                .map_err(|err| format!("level {}: {}", level.name, err))?;
+        }
+        Ok(())
+    }
+}
+
+// Number of levels completed in order, the next one is unlocked.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Progress {
+    pub completed: usize,
+}
+
+impl Progress {
+    fn save(&self) -> Result<(), config::Error> {
+        config::write_to(PROGRESS_FILE, self)
+    }
+}
+
+#[derive(Default)]
+pub struct Campaign {
+    pub levels: Vec<Level>,
+    pub progress: Progress,
+    current: Option<usize>,
+    skirmish: Option<(Config, MapDefinition)>,
+}
+
+impl Campaign {
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level <= self.progress.completed
+    }
+
+    pub fn current(&self) -> Option<&Level> {
+        self.current.and_then(|level| self.levels.get(level))
+    }
+
+    pub fn current_level(&self) -> Option<usize> {
+        self.current
+    }
+
+    // a new config from the files, kept for after the level when one is being played
+    pub fn reload(&mut self, skirmish_config: Config, config: &mut Config) {
+        let levels = &self.levels;
+        let level = self.current.and_then(|level| levels.get(level));
+        match (level, self.skirmish.as_mut()) {
+            (Some(level), Some(skirmish)) => {
+                let mut level_config = skirmish_config.clone();
+                level.config.apply(&mut level_config);
+                *config = level_config;
+                skirmish.0 = skirmish_config;
+            }
+            _ => *config = skirmish_config,
+        }
+    }
+
+    // swap the skirmish config and map for those of the level until it ends
+    pub fn start(&mut self, level: usize, config: &mut Config, map: &mut MapDefinition) {
+        let skirmish = self
+            .skirmish
+            .get_or_insert_with(|| (config.clone(), map.clone()));
+        let mut level_config = skirmish.0.clone();
+        self.levels[level].config.apply(&mut level_config);
+        *config = level_config;
+        *map = self.levels[level].map.clone();
+        self.current = Some(level);
+    }
+
+    fn finish(&mut self, won: bool, config: &mut Config, map: &mut MapDefinition) {
+        if let Some(level) = self.current.take() {
+            if won && level + 1 > self.progress.completed {
+                self.progress.completed = level + 1;
+                info!("campaign: level {} completed", level + 1);
+                if let Err(err) = self.progress.save() {
+                    warn!("could not save campaign progress: {}", err);
+                }
+            }
+        }
+        if let Some((skirmish_config, skirmish_map)) = self.skirmish.take() {
+            *config = skirmish_config;
+            *map = skirmish_map;
+        }
+    }
+}
+
+fn load_campaign(mut campaign: ResMut<Campaign>) {
+    let levels = match config::read_from::<Levels>(CAMPAIGN_FILE)
+        .map_err(|err| err.to_string())
+        .and_then(|levels| levels.check().map(|_| levels))
+    {
+        Ok(levels) => levels,
+        Err(err) => {
+            warn!(
+                "could not load {}, using the default campaign: {}",
+                CAMPAIGN_FILE, err
+            );
+            Levels::default()
+        }
+    };
+    campaign.levels = levels.levels;
+    campaign.progress = config::read_from(PROGRESS_FILE).unwrap_or_else(|err| {
+        warn!("could not load campaign progress: {}", err);
+        Progress::default()
+    });
+}
+
+fn win_condition(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut game: ResMut<Game>,
+    campaign: Res<Campaign>,
+    moons: Query<&OwnedBy, With<Moon>>,
+) {
+    if game_screen.current_screen != crate::Screen::Game || game.state != GameState::Play {
+        return;
+    }
+    let won = match campaign.current().map(|level| &level.win) {
+        Some(WinCondition::FreeAllMoons) => {
+            moons.iter().next().is_some() && moons.iter().all(|owner| *owner == OwnedBy::Player(0))
+        }
+        Some(WinCondition::Survive(seconds)) => game.elapsed >= *seconds,
+        _ => false,
+    };
+    if won {
+        game.state = GameState::Win;
+        game_screen.current_screen = crate::Screen::End;
+    }
+}
+
+fn finish_level(
+    game_screen: Res<crate::GameScreen>,
+    game: Res<Game>,
+    mut campaign: ResMut<Campaign>,
+    mut config: ResMut<Config>,
+    mut map: ResMut<MapDefinition>,
+    match_start: Res<crate::game::MatchStart>,
+) {
+    if campaign.current.is_some()
+        && game_screen.current_screen != crate::Screen::Game
+        && *match_start != crate::game::MatchStart::Suspended
+    {
+        campaign.finish(game.state == GameState::Win, &mut config, &mut map);
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+pub const SPEEDS: [f32; 4] = [0.5, 1., 2., 4.];
+
+// Match time, advanced once per frame. Gameplay systems read it instead of `Time`
+// so that a match only depends on its seed, its orders and the frame deltas.
+#[derive(Debug)]
+pub struct GameClock {
+    tick: u64,
+    delta: f32,
+    elapsed: f32,
+    script: Vec<f32>,
+    // a paused clock doesn't tick, nothing moves in the match
+    paused: bool,
+    speed: f32,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            tick: 0,
+            delta: 0.,
+            elapsed: 0.,
+            script: vec![],
+            paused: false,
+            speed: 1.,
+        }
+    }
+}
+
+impl GameClock {
+    // A clock replaying recorded frame deltas, falling back to real time once they run out.
+    pub fn scripted(deltas: Vec<f32>) -> Self {
+        GameClock {
+            script: deltas,
+            ..Default::default()
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn seconds_since_start(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    // next speed in `SPEEDS`, or the previous one when `faster` is false
+    pub fn change_speed(&mut self, faster: bool) {
+        let current = SPEEDS
+            .iter()
+            .position(|speed| *speed == self.speed)
+            .unwrap_or(1);
+        let next = if faster {
+            usize::min(current + 1, SPEEDS.len() - 1)
+        } else {
+            current.saturating_sub(1)
+        };
+        self.speed = SPEEDS[next];
+    }
+
+    // back to real time, for the matches after a replay
+    pub fn clear_script(&mut self) {
+        self.script.clear();
+    }
+
+    pub fn reset(&mut self) {
+        let script = std::mem::take(&mut self.script);
+        *self = GameClock::scripted(script);
+    }
+
+    // continue a saved match from where it was
+    pub fn restore(&mut self, tick: u64, elapsed: f32) {
+        self.reset();
+        self.tick = tick;
+        self.elapsed = elapsed;
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        if self.paused {
+            self.delta = 0.;
+            return;
+        }
+        // recorded deltas already have the speed they were played at
+        let delta = self
+            .script
+            .get(self.tick as usize)
+            .copied()
+            .unwrap_or(delta * self.speed);
+        self.tick += 1;
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+pub fn tick_clock(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    clock.advance(time.delta_seconds());
+}
+
+// physics steps over the match time, and stops with it
+pub fn step_physics(
+    clock: Res<GameClock>,
+    mut configuration: ResMut<bevy_rapier2d::physics::RapierConfiguration>,
+    mut integration_parameters: ResMut<bevy_rapier2d::rapier::dynamics::IntegrationParameters>,
+) {
+    let delta = clock.delta_seconds();
+    configuration.physics_pipeline_active = delta > 0.;
+    if delta > 0. {
+        integration_parameters.set_dt(delta);
+    }
+}
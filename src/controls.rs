@@ -0,0 +1,405 @@
+use bevy::{input::gamepad::GamepadButton, prelude::*};
+use tracing::info;
+
+use crate::input::{Action, Binding};
+
+const CURRENT_SCREEN: crate::Screen = crate::Screen::Controls;
+
+struct ScreenTag;
+
+struct Screen {
+    loaded: bool,
+    selected: Option<usize>,
+    // action waiting for its new binding, the first frame is skipped so that the click or key
+    // that started listening is not taken
+    listening: Option<(Action, bool)>,
+}
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            loaded: false,
+            selected: None,
+            listening: None,
+        }
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Screen::default())
+            .add_system(setup)
+            .add_system(keyboard_input_system)
+            .add_system(button_system)
+            .add_system(listen_system)
+            .add_system(display_bindings)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ControlsButton {
+    Rebind(Action),
+    Reset,
+    Back,
+}
+
+impl Into<String> for ControlsButton {
+    fn into(self) -> String {
+        match self {
+            ControlsButton::Rebind(action) => action.to_string(),
+            ControlsButton::Reset => "Reset".to_string(),
+            ControlsButton::Back => "Back".to_string(),
+        }
+    }
+}
+
+fn controls_buttons() -> Vec<ControlsButton> {
+    let mut buttons = Action::ALL
+        .iter()
+        .map(|action| ControlsButton::Rebind(*action))
+        .collect::<Vec<_>>();
+    buttons.push(ControlsButton::Reset);
+    buttons.push(ControlsButton::Back);
+    buttons
+}
+
+struct BindingsLabel(usize);
+
+fn setup(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    asset_server: Res<AssetServer>,
+    mut nine_patches: ResMut<Assets<bevy_ninepatch::NinePatchBuilder<()>>>,
+    mut buttons: ResMut<Assets<crate::ui::button::Button>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    match_start: Res<crate::game::MatchStart>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
+        info!("Loading screen");
+
+        if *match_start == crate::game::MatchStart::Suspended {
+            let backdrop = crate::game::pause::spawn_backdrop(commands, &mut materials);
+            commands.insert_one(backdrop, ScreenTag);
+        }
+
+        let button_handle = asset_handles.get_button_handle(
+            &asset_server,
+            &mut materials,
+            &mut nine_patches,
+            &mut buttons,
+        );
+        let button = buttons.get(&button_handle).unwrap();
+
+        let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+        let font_sub: Handle<Font> = asset_handles.get_font_sub_handle(&asset_server);
+
+        let color_none = materials.add(Color::NONE.into());
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(10.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(5.),
+                    },
+                    size: Size::<Val> {
+                        height: Val::Px(75.),
+                        width: Val::Auto,
+                    },
+                    ..Default::default()
+                },
+                material: color_none.clone(),
+                ..Default::default()
+            })
+            .with_children(|title_parent| {
+                title_parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(75.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "Controls".to_string(),
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT,
+                            font_size: 75.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+            })
+            .with(ScreenTag);
+
+        let rows = controls_buttons()
+            .into_iter()
+            .enumerate()
+            .map(|(i, controls_button)| {
+                let row = commands
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            margin: Rect {
+                                bottom: Val::Px(5.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        material: color_none.clone(),
+                        ..Default::default()
+                    })
+                    .current_entity()
+                    .unwrap();
+                let label = button.add(
+                    commands,
+                    150.,
+                    30.,
+                    Rect::all(Val::Auto),
+                    font.clone(),
+                    controls_button,
+                    15.,
+                );
+                commands
+                    .spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(15.),
+                                ..Default::default()
+                            },
+                            margin: Rect {
+                                left: Val::Px(20.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: "".to_string(),
+                            font: font_sub.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT,
+                                font_size: 15.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    })
+                    .with(BindingsLabel(i));
+                let bindings = commands.current_entity().unwrap();
+                commands.push_children(row, &[label, bindings]);
+                row
+            })
+            .collect::<Vec<_>>();
+
+        // three columns to fit all the actions
+        let per_column = (rows.len() + 2) / 3;
+        for (column, rows) in rows.chunks(per_column).enumerate() {
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect::<Val> {
+                            left: Val::Percent(5. + 31. * column as f32),
+                            right: Val::Undefined,
+                            bottom: Val::Undefined,
+                            top: Val::Percent(18.),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    material: color_none.clone(),
+                    ..Default::default()
+                })
+                .with(ScreenTag);
+            let list = commands.current_entity().unwrap();
+            commands.push_children(list, rows);
+        }
+
+        screen.selected = None;
+        screen.listening = None;
+        screen.loaded = true;
+    }
+}
+
+fn tear_down(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    query: Query<Entity, With<ScreenTag>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN && screen.loaded {
+        info!("tear down");
+
+        for entity in query.iter() {
+            commands.despawn_recursive(entity);
+        }
+
+        screen.loaded = false;
+    }
+}
+
+fn select(
+    button: ControlsButton,
+    game_screen: &mut crate::GameScreen,
+    screen: &mut Screen,
+    settings: &mut crate::Settings,
+) {
+    match button {
+        ControlsButton::Rebind(action) => screen.listening = Some((action, false)),
+        ControlsButton::Reset => {
+            settings.bindings = crate::input::Bindings::default();
+            crate::settings::save(settings);
+        }
+        ControlsButton::Back => game_screen.current_screen = crate::Screen::Settings,
+    }
+}
+
+fn keyboard_input_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    actions: Res<crate::input::Actions>,
+    mut settings: ResMut<crate::Settings>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && screen.loaded && screen.listening.is_none() {
+        let count = controls_buttons().len();
+        if actions.just_released(Action::Back) {
+            game_screen.current_screen = crate::Screen::Settings;
+        } else if actions.just_released(Action::Down) {
+            screen.selected = Some(
+                screen
+                    .selected
+                    .map(|i| usize::min(count - 1, i + 1))
+                    .unwrap_or(0),
+            );
+        } else if actions.just_released(Action::Up) {
+            screen.selected = Some(screen.selected.map(|i| i.saturating_sub(1)).unwrap_or(0));
+        } else if actions.just_released(Action::Confirm) {
+            if let Some(button) = screen
+                .selected
+                .and_then(|i| controls_buttons().get(i).copied())
+            {
+                select(button, &mut game_screen, &mut screen, &mut settings);
+            }
+        }
+    }
+}
+
+fn button_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    mut settings: ResMut<crate::Settings>,
+    mut interaction_query: Query<
+        (&Interaction, &crate::ui::button::ButtonId<ControlsButton>),
+        (With<Button>, Mutated<Interaction>),
+    >,
+) {
+    if screen.listening.is_some() {
+        return;
+    }
+    for (interaction, button_id) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                select(button_id.0, &mut game_screen, &mut screen, &mut settings)
+            }
+            Interaction::Hovered => {
+                screen.selected = controls_buttons()
+                    .iter()
+                    .position(|button| *button == button_id.0)
+            }
+            Interaction::None => screen.selected = None,
+        }
+    }
+}
+
+// the first key, mouse or gamepad button pressed replaces the binding of the same device
+fn listen_system(
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    (mut settings, actions): (ResMut<crate::Settings>, Res<crate::input::Actions>),
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN {
+        return;
+    }
+    let action = match screen.listening {
+        Some((action, true)) => action,
+        Some((action, false)) => {
+            screen.listening = Some((action, true));
+            return;
+        }
+        None => return,
+    };
+    // Back cancels once released, so that the same release doesn't leave the screen
+    if actions.just_released(Action::Back) {
+        screen.listening = None;
+        return;
+    }
+    if actions.pressed(Action::Back) {
+        return;
+    }
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse_button_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.1))
+        });
+    if let Some(binding) = binding {
+        info!("binding {} to {}", binding, action);
+        settings.bindings.rebind(action, binding);
+        crate::settings::save(&settings);
+        screen.listening = None;
+    }
+}
+
+fn display_bindings(
+    screen: Res<Screen>,
+    settings: Res<crate::Settings>,
+    mut query: Query<(&BindingsLabel, &mut Text)>,
+) {
+    let buttons = controls_buttons();
+    for (label, mut text) in query.iter_mut() {
+        text.value = match buttons[label.0] {
+            ControlsButton::Rebind(action) => {
+                if screen.listening.map(|(listening, _)| listening) == Some(action) {
+                    "press a key or a button, or Back to cancel...".to_string()
+                } else {
+                    settings
+                        .bindings
+                        .of(action)
+                        .iter()
+                        .map(|binding| binding.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            }
+            ControlsButton::Reset => "restore the default controls".to_string(),
+            ControlsButton::Back => "".to_string(),
+        };
+        text.style.color = if screen.selected == Some(label.0) {
+            crate::ui::ColorScheme::TEXT_HIGHLIGHT
+        } else {
+            crate::ui::ColorScheme::TEXT
+        };
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::Ratio;
+use crate::{Config, ConfigOverrides};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+            Difficulty::Insane => write!(f, "Insane"),
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Insane,
+            Difficulty::Insane => Difficulty::Easy,
+        }
+    }
+
+    // changes from the default `Config`, which is `Normal`
+    pub fn preset(&self) -> ConfigOverrides {
+        match self {
+            Difficulty::Easy => ConfigOverrides {
+                bigger_player_ship_rate: Some(0.9),
+                fleet_delay: Some(10.),
+                fleet_chance: Some(0.25),
+                asteroid_chance: Some(0.3),
+                planet_missile_timer: Some(15.),
+                fleet_override_chance: Some(0.5),
+                fleet_min_health: Some(-5),
+                fleet_health_divisor: Some(2.),
+                swarm_size: Some(40),
+                swarm_ratio: Some(Ratio::Half),
+                ..Default::default()
+            },
+            Difficulty::Normal => ConfigOverrides::default(),
+            Difficulty::Hard => ConfigOverrides {
+                bigger_player_ship_rate: Some(0.6),
+                fleet_delay: Some(4.),
+                fleet_chance: Some(0.5),
+                asteroid_timer: Some(25.),
+                asteroid_chance: Some(0.6),
+                planet_missile_timer: Some(8.),
+                fleet_override_chance: Some(0.85),
+                fleet_min_health: Some(-3),
+                fleet_health_divisor: Some(1.25),
+                swarm_size: Some(20),
+                ..Default::default()
+            },
+            Difficulty::Insane => ConfigOverrides {
+                bigger_player_ship_rate: Some(0.5),
+                bigger_player_ship_change: Some(1.1),
+                fleet_timer: Some(1.5),
+                fleet_delay: Some(2.),
+                fleet_chance: Some(0.6),
+                asteroid_timer: Some(20.),
+                asteroid_chance: Some(0.7),
+                planet_missile_timer: Some(6.),
+                fleet_override_chance: Some(1.),
+                fleet_min_health: Some(-2),
+                fleet_health_divisor: Some(1.),
+                swarm_size: Some(15),
+                swarm_ratio: Some(Ratio::All),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+// How the `Config` is built: defaults, then the difficulty preset, then the keys set in
+// `config.conf`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLayers {
+    pub difficulty: Difficulty,
+    pub user: ConfigOverrides,
+}
+
+impl ConfigLayers {
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        self.difficulty.preset().apply(&mut config);
+        self.user.apply(&mut config);
+        config
+    }
+}
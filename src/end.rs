@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-use tracing::info;
+use tracing::{info, warn};
+
+use crate::input::Action;
 
 const CURRENT_SCREEN: crate::Screen = crate::Screen::End;
 
@@ -14,60 +16,113 @@ impl Default for Screen {
     }
 }
 
-#[derive(Default)]
-pub struct GameStats {}
+fn side_name(owner: &crate::game::OwnedBy) -> String {
+    match owner {
+        crate::game::OwnedBy::Player(0) => "you".to_string(),
+        crate::game::OwnedBy::Player(n) => format!("player {}", n + 1),
+        crate::game::OwnedBy::Neutral => "planet".to_string(),
+    }
+}
 
 pub struct Plugin;
 impl bevy::app::Plugin for Plugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_resource(Screen::default())
-            .init_resource::<GameStats>()
             .add_system(input_system)
             .add_system(setup)
-            .add_system(update_stats)
             .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
     }
 }
 
-fn update_stats(
-    mut _stats: ResMut<GameStats>,
-    _game: Res<crate::game::Game>,
-    (mut interesting_event_reader, interesting_events): (
-        Local<EventReader<crate::game::InterestingEvent>>,
-        ResMut<Events<crate::game::InterestingEvent>>,
-    ),
-) {
-    for event in interesting_event_reader.iter(&interesting_events) {
-        match event {
-            _ => (),
-        }
-    }
-}
+const STATS_COLUMNS: &[(&str, f32)] = &[
+    ("", 110.),
+    ("built", 90.),
+    ("lost", 80.),
+    ("destroyed", 120.),
+    ("moons taken", 140.),
+    ("moons lost", 130.),
+    ("largest fleet", 150.),
+    ("peak ships", 120.),
+];
 
 fn setup(
     commands: &mut Commands,
-    game_screen: Res<crate::GameScreen>,
+    mut game_screen: ResMut<crate::GameScreen>,
     mut screen: ResMut<Screen>,
     mut game: ResMut<crate::game::Game>,
+    rng: Res<crate::rng::MatchRng>,
+    (mut highscores, config_layers, replay, stats): (
+        ResMut<crate::highscores::Highscores>,
+        Res<crate::difficulty::ConfigLayers>,
+        Res<crate::replay::ReplayState>,
+        Res<crate::game::stats::GameStats>,
+    ),
     asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    (mut materials, mut meshes, windows): (
+        ResMut<Assets<ColorMaterial>>,
+        ResMut<Assets<Mesh>>,
+        Res<Windows>,
+    ),
     mut asset_handles: ResMut<crate::AssetHandles>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
         info!("Loading screen");
 
         let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+        let font_sub: Handle<Font> = asset_handles.get_font_sub_handle(&asset_server);
+        let color_none = materials.add(Color::NONE.into());
+
+        timeline(
+            commands,
+            &stats,
+            windows.get_primary().unwrap(),
+            font_sub.clone(),
+            &mut materials,
+            &mut meshes,
+            &mut asset_handles,
+        );
+
+        let score = game.score as u32;
+        // watching a replay doesn't make it to the highscores
+        let (new_highscore, rank) = if replay.is_playback() {
+            (false, None)
+        } else {
+            let new_highscore = game_screen.is_new_highscore(score);
+            let rank = highscores.insert(crate::highscores::Entry {
+                score,
+                time: game.elapsed,
+                outcome: game.state,
+                date: crate::highscores::now(),
+                seed: rng.seed(),
+                difficulty: config_layers.difficulty,
+            });
+            if rank.is_some() {
+                if let Err(err) = highscores.save() {
+                    warn!("could not save highscores: {}", err);
+                }
+            }
+            game_screen.highscore = highscores.best();
+            (new_highscore, rank)
+        };
 
         commands
             .spawn(NodeBundle {
                 style: Style {
-                    margin: Rect::all(Val::Auto),
+                    // the bottom of the screen is left to the timeline
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Px(0.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(3.),
+                    },
+                    size: Size::new(Val::Percent(100.), Val::Auto),
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
                     flex_direction: FlexDirection::ColumnReverse,
                     ..Default::default()
                 },
-                material: materials.add(Color::NONE.into()),
+                material: color_none.clone(),
                 ..Default::default()
             })
             .with(ScreenTag)
@@ -104,8 +159,8 @@ fn setup(
                         ..Default::default()
                     },
                     text: Text {
-                        value: format!("final score: {}", game.score as u32),
-                        font,
+                        value: format!("final score: {}", score),
+                        font: font.clone(),
                         style: TextStyle {
                             color: crate::ui::ColorScheme::TEXT,
                             font_size: 60.,
@@ -114,6 +169,175 @@ fn setup(
                     },
                     ..Default::default()
                 });
+                if let Some(rank) = rank {
+                    parent.spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(35.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: if new_highscore {
+                                "new highscore!".to_string()
+                            } else {
+                                format!("#{} in the highscores", rank + 1)
+                            },
+                            font: font.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT_HIGHLIGHT,
+                                font_size: 35.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    });
+                }
+                let header = STATS_COLUMNS
+                    .iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect::<Vec<_>>();
+                let rows = stats
+                    .sides()
+                    .into_iter()
+                    .map(|(owner, side)| {
+                        vec![
+                            side_name(owner),
+                            format!("{}", side.built),
+                            format!("{}", side.lost),
+                            format!("{}", side.destroyed),
+                            format!("{}", side.moons_captured),
+                            format!("{}", side.moons_lost),
+                            format!("{}", side.largest_fleet),
+                            format!("{}", side.peak_ships),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            margin: Rect {
+                                top: Val::Px(20.),
+                                bottom: Val::Px(10.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        material: color_none.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|table_parent| {
+                        for (i, row) in std::iter::once(&header).chain(rows.iter()).enumerate() {
+                            table_parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Row,
+                                        margin: Rect {
+                                            bottom: Val::Px(5.),
+                                            ..Default::default()
+                                        },
+                                        ..Default::default()
+                                    },
+                                    material: color_none.clone(),
+                                    ..Default::default()
+                                })
+                                .with_children(|row_parent| {
+                                    for (cell, (_, width)) in row.iter().zip(STATS_COLUMNS.iter()) {
+                                        row_parent.spawn(TextBundle {
+                                            style: Style {
+                                                size: Size {
+                                                    width: Val::Px(*width),
+                                                    height: Val::Px(22.),
+                                                },
+                                                ..Default::default()
+                                            },
+                                            text: Text {
+                                                value: cell.clone(),
+                                                font: font_sub.clone(),
+                                                style: TextStyle {
+                                                    color: if i == 0 {
+                                                        crate::ui::ColorScheme::TEXT_DIM
+                                                    } else {
+                                                        crate::ui::ColorScheme::TEXT
+                                                    },
+                                                    font_size: 22.,
+                                                    ..Default::default()
+                                                },
+                                            },
+                                            ..Default::default()
+                                        });
+                                    }
+                                });
+                        }
+                    });
+                let never = || "-".to_string();
+                let lines = vec![
+                    format!(
+                        "match time: {}",
+                        crate::highscores::format_time(game.elapsed)
+                    ),
+                    format!(
+                        "first moon captured: {}",
+                        stats
+                            .first_capture
+                            .map(crate::highscores::format_time)
+                            .unwrap_or_else(never)
+                    ),
+                    format!(
+                        "planet shield dropped: {}",
+                        stats
+                            .shield_drop
+                            .map(crate::highscores::format_time)
+                            .unwrap_or_else(never)
+                    ),
+                    format!("asteroids survived: {}", stats.asteroids_survived),
+                ];
+                for line in lines {
+                    parent.spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(22.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: line,
+                            font: font_sub.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT,
+                                font_size: 22.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    });
+                }
+                parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(25.),
+                            ..Default::default()
+                        },
+                        margin: Rect {
+                            top: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: format!("seed: {}", rng.seed()),
+                        font,
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT_DIM,
+                            font_size: 25.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
             });
 
         *game = crate::game::Game::default();
@@ -122,6 +346,208 @@ fn setup(
     }
 }
 
+const CHART_WIDTH: f32 = 800.;
+const CHART_HEIGHT: f32 = 140.;
+// between the chart and the bottom of the window
+const CHART_MARGIN: f32 = 40.;
+
+fn stroke_options(width: f32) -> bevy_prototype_lyon::prelude::StrokeOptions {
+    bevy_prototype_lyon::prelude::StrokeOptions::default()
+        .with_line_width(width)
+        .with_line_cap(bevy_prototype_lyon::prelude::LineCap::Round)
+        .with_line_join(bevy_prototype_lyon::prelude::LineJoin::Round)
+}
+
+// Ships orbiting the moons during the match, the player against everyone else, with a dot where
+// a moon was captured and a tick when the planet sent a fleet.
+fn timeline(
+    commands: &mut Commands,
+    stats: &crate::game::stats::GameStats,
+    window: &Window,
+    font: Handle<Font>,
+    materials: &mut Assets<ColorMaterial>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    asset_handles: &mut crate::AssetHandles,
+) {
+    let (first, last) = match (stats.timeline.first(), stats.timeline.last()) {
+        (Some(first), Some(last)) if last.time > first.time => (first.time, last.time),
+        _ => return,
+    };
+    let player = crate::game::OwnedBy::Player(0);
+    let most_ships = stats
+        .timeline
+        .iter()
+        .map(|sample| {
+            sample
+                .ships(&player, false)
+                .max(sample.ships(&player, true))
+        })
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let width = CHART_WIDTH.min(window.width() as f32 * 0.8);
+    let origin = Vec3::new(
+        -width / 2.,
+        -(window.height() as f32) / 2. + CHART_MARGIN,
+        crate::Z_SHIP,
+    );
+    let x = |time: f32| (time - first) / (last - first) * width;
+    let y = |ships: usize| ships as f32 / most_ships as f32 * CHART_HEIGHT;
+    let point = bevy_prototype_lyon::prelude::point;
+
+    let color_player = asset_handles.get_color_spawning_self(materials);
+    let color_others = asset_handles.get_color_spawning_enemy(materials);
+    let color_axis = materials.add(crate::ui::ColorScheme::TEXT_DIM.into());
+    let color_marker = materials.add(crate::ui::ColorScheme::TEXT_HIGHLIGHT.into());
+
+    let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+    builder.move_to(point(0., CHART_HEIGHT));
+    builder.line_to(point(0., 0.));
+    builder.line_to(point(width, 0.));
+    let axes = builder
+        .build()
+        .stroke(color_axis, meshes, origin, &stroke_options(2.));
+    commands.spawn(axes).with(ScreenTag);
+
+    // a match resumed after a restart has no timeline before it
+    let shown = |time: &f32| *time >= first && *time <= last;
+
+    for &time in stats.fleet_waves.iter().filter(|time| shown(time)) {
+        let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+        builder.move_to(point(x(time), 0.));
+        builder.line_to(point(x(time), -8.));
+        let tick =
+            builder
+                .build()
+                .stroke(color_others.clone(), meshes, origin, &stroke_options(2.));
+        commands.spawn(tick).with(ScreenTag);
+    }
+
+    for (others, color) in [(false, color_player.clone()), (true, color_others.clone())].iter() {
+        let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+        for (i, sample) in stats.timeline.iter().enumerate() {
+            let at = point(x(sample.time), y(sample.ships(&player, *others)));
+            if i == 0 {
+                builder.move_to(at);
+            } else {
+                builder.line_to(at);
+            }
+        }
+        let line = builder
+            .build()
+            .stroke(color.clone(), meshes, origin, &stroke_options(3.));
+        commands.spawn(line).with(ScreenTag);
+    }
+
+    for (time, owner) in stats.captures.iter().filter(|(time, _)| shown(time)) {
+        let others = *owner != player;
+        // on the line of the side that took the moon, at the sample just before
+        let ships = stats
+            .timeline
+            .iter()
+            .take_while(|sample| sample.time <= *time)
+            .last()
+            .map(|sample| sample.ships(&player, others))
+            .unwrap_or(0);
+        let (center_x, center_y) = (x(*time), y(ships));
+        let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+        builder.move_to(point(center_x + 4., center_y));
+        builder.arc(
+            point(center_x, center_y),
+            4.,
+            4.,
+            2. * std::f32::consts::PI,
+            0.,
+        );
+        let dot = builder.build().stroke(
+            if others {
+                color_others.clone()
+            } else {
+                color_marker.clone()
+            },
+            meshes,
+            origin + Vec3::new(0., 0., 0.1),
+            &stroke_options(3.),
+        );
+        commands.spawn(dot).with(ScreenTag);
+    }
+
+    let legend = vec![
+        (
+            "you".to_string(),
+            materials.get(&color_player).unwrap().color,
+        ),
+        (
+            "others".to_string(),
+            materials.get(&color_others).unwrap().color,
+        ),
+        (
+            "o moon captured".to_string(),
+            crate::ui::ColorScheme::TEXT_HIGHLIGHT,
+        ),
+        (
+            "| planet fleet".to_string(),
+            materials.get(&color_others).unwrap().color,
+        ),
+        (
+            format!(
+                "up to {} ships, over {}",
+                most_ships,
+                crate::highscores::format_time(last - first)
+            ),
+            crate::ui::ColorScheme::TEXT_DIM,
+        ),
+    ];
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect::<Val> {
+                    left: Val::Px(0.),
+                    right: Val::Undefined,
+                    bottom: Val::Px(CHART_MARGIN + CHART_HEIGHT + 10.),
+                    top: Val::Undefined,
+                },
+                size: Size::new(Val::Percent(100.), Val::Px(20.)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Row,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with(ScreenTag)
+        .with_children(|legend_parent| {
+            for (label, color) in legend {
+                legend_parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(20.),
+                            ..Default::default()
+                        },
+                        margin: Rect {
+                            left: Val::Px(10.),
+                            right: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: label,
+                        font: font.clone(),
+                        style: TextStyle {
+                            color,
+                            font_size: 20.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+            }
+        });
+}
+
 fn tear_down(
     commands: &mut Commands,
     game_screen: Res<crate::GameScreen>,
@@ -142,14 +568,13 @@ fn tear_down(
 fn input_system(
     mut game_screen: ResMut<crate::GameScreen>,
     screen: Res<Screen>,
-    mouse_button_input: Res<Input<MouseButton>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<crate::input::Actions>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN
         && screen.loaded
-        && (mouse_button_input.just_pressed(MouseButton::Left)
-            || keyboard_input.just_released(KeyCode::Escape)
-            || keyboard_input.just_released(KeyCode::Space))
+        && (actions.just_pressed(Action::Select)
+            || actions.just_released(Action::Back)
+            || actions.just_released(Action::Confirm))
     {
         game_screen.current_screen = crate::Screen::Menu;
     }
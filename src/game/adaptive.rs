@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{GameEvents, InterestingEvent, OwnedBy};
+
+// how often the match is looked at, in seconds
+const REVIEW_EVERY: f32 = 10.;
+const STEP: f32 = 0.1;
+
+// Follows how the match goes for the player to make the planet harder or easier on them,
+// when `Config::adaptive_difficulty` is on. `factor` scales the planet fleets budget and
+// how fast asteroids come.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Adaptation {
+    pub factor: f32,
+    last_capture: f32,
+    last_review: f32,
+    losses: usize,
+}
+
+impl Default for Adaptation {
+    fn default() -> Self {
+        Adaptation {
+            factor: 1.,
+            last_capture: 0.,
+            last_review: 0.,
+            losses: 0,
+        }
+    }
+}
+
+impl Adaptation {
+    pub fn factor(&self, config: &crate::Config) -> f32 {
+        if config.adaptive_difficulty {
+            self.factor
+        } else {
+            1.
+        }
+    }
+
+    // each signal says the player is doing well (1), badly (-1) or neither (0)
+    fn review(&mut self, now: f32, player_ships: usize, other_ships: usize) -> i32 {
+        let ratio = player_ships as f32 / other_ships.max(1) as f32;
+        let strength = if ratio > 1.5 {
+            1
+        } else if ratio < 0.5 {
+            -1
+        } else {
+            0
+        };
+        let since_capture = now - self.last_capture;
+        let momentum = if since_capture < 20. {
+            1
+        } else if since_capture > 60. {
+            -1
+        } else {
+            0
+        };
+        // about as many ships as a moon builds in the review period
+        let attrition = if self.losses > 10 { -1 } else { 0 };
+        self.losses = 0;
+        self.last_review = now;
+        strength + momentum + attrition
+    }
+}
+
+pub fn adapt(
+    game_screen: Res<crate::GameScreen>,
+    screen: Res<super::Screen>,
+    (clock, config): (Res<crate::clock::GameClock>, Res<crate::Config>),
+    mut adaptation: ResMut<Adaptation>,
+    (mut event_reader, events): (Local<EventReader<GameEvents>>, Res<Events<GameEvents>>),
+    (mut interesting_reader, interesting_events): (
+        Local<EventReader<InterestingEvent>>,
+        Res<Events<InterestingEvent>>,
+    ),
+    ships: Query<&OwnedBy, With<crate::space::Ship>>,
+) {
+    if game_screen.current_screen != super::CURRENT_SCREEN || !config.adaptive_difficulty {
+        return;
+    }
+    let now = clock.seconds_since_start();
+    if !screen.loaded {
+        *adaptation = Adaptation {
+            last_capture: now,
+            last_review: now,
+            ..Default::default()
+        };
+        return;
+    }
+
+    for event in event_reader.iter(&events) {
+        if let GameEvents::MoonConquered(_, OwnedBy::Player(0)) = event {
+            adaptation.last_capture = now;
+        }
+    }
+    for event in interesting_reader.iter(&interesting_events) {
+        if let InterestingEvent::ShipDestroyed {
+            owner: OwnedBy::Player(0),
+            ..
+        } = event
+        {
+            adaptation.losses += 1;
+        }
+    }
+
+    let player_ships = ships
+        .iter()
+        .filter(|owner| **owner == OwnedBy::Player(0))
+        .count();
+    if now - adaptation.last_review >= REVIEW_EVERY {
+        let other_ships = ships.iter().count() - player_ships;
+        let nudge = adaptation.review(now, player_ships, other_ships);
+        adaptation.factor = (adaptation.factor + nudge as f32 * STEP)
+            .max(config.adaptive_min)
+            .min(config.adaptive_max);
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{prelude::IteratorRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::{Game, Moon, Order, OwnedBy, Planet, Ratio};
+
+// What a computer-controlled side knows about the match when it thinks.
+pub struct Snapshot {
+    pub elapsed: f32,
+    pub moons: Vec<MoonView>,
+    pub in_transit: Vec<ShipInTransit>,
+    pub planet: Option<PlanetView>,
+}
+
+pub struct MoonView {
+    pub entity: Entity,
+    pub index: i32,
+    pub owner: OwnedBy,
+    pub ships: HashMap<OwnedBy, usize>,
+}
+
+impl MoonView {
+    pub fn ships_of(&self, owner: &OwnedBy) -> usize {
+        self.ships.get(owner).copied().unwrap_or(0)
+    }
+
+    pub fn ships_against(&self, owner: &OwnedBy) -> usize {
+        self.ships
+            .iter()
+            .filter(|(ships_owner, _)| *ships_owner != owner)
+            .map(|(_, count)| *count)
+            .sum()
+    }
+}
+
+pub struct ShipInTransit {
+    pub owner: OwnedBy,
+    pub from: Entity,
+    pub towards: Entity,
+    pub hit_points: i32,
+}
+
+pub struct PlanetView {
+    pub entity: Entity,
+    // ships bounce off the planet while it is shielded or some moons are still neutral
+    pub shielded: bool,
+}
+
+impl Snapshot {
+    pub fn moons_of<'a>(&'a self, owner: &'a OwnedBy) -> impl Iterator<Item = &'a MoonView> {
+        self.moons.iter().filter(move |moon| moon.owner == *owner)
+    }
+
+    pub fn incoming(&self, moon: Entity, owner: &OwnedBy) -> usize {
+        self.in_transit
+            .iter()
+            .filter(|ship| ship.towards == moon && ship.owner == *owner)
+            .count()
+    }
+
+    pub fn incoming_against(&self, moon: Entity, owner: &OwnedBy) -> usize {
+        self.in_transit
+            .iter()
+            .filter(|ship| ship.towards == moon && ship.owner != *owner)
+            .count()
+    }
+}
+
+pub struct AiOrder {
+    pub from: Entity,
+    pub towards: Entity,
+    pub ratio: Ratio,
+}
+
+// Drives a computer-controlled side. It is asked for orders every tick and keeps its own
+// pace, orders are then executed like the player's. The side owning the planet is also asked
+// about the fleets it launches, whose size is set by the difficulty.
+pub trait AiController: Send + Sync {
+    fn think(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Vec<AiOrder>;
+
+    // called when the fleet timer of the planet is up, `chance` comes from the config
+    fn launch_fleet(
+        &mut self,
+        _owner: &OwnedBy,
+        _snapshot: &Snapshot,
+        chance: f32,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        rng.gen_bool(chance as f64)
+    }
+
+    // moon a ship of a launched fleet flies to, a random one so that it can't be foreseen
+    fn fleet_target(
+        &mut self,
+        _owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Option<Entity> {
+        snapshot.moons.iter().choose(rng).map(|moon| moon.entity)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    // keeps its ships home and only strikes with an overwhelming advantage
+    Turtle,
+    // attacks as soon as it can, with everything it has
+    Rush,
+    // goes after the weakest moons, especially while they are under attack
+    Opportunist,
+    // swarms the player with what gathers around the moons
+    Swarm,
+}
+
+impl Strategy {
+    pub fn controller(&self, config: &crate::Config) -> Box<dyn AiController> {
+        match self {
+            Strategy::Turtle => Box::new(Turtle::default()),
+            Strategy::Rush => Box::new(Rush::default()),
+            Strategy::Opportunist => Box::new(Opportunist::default()),
+            Strategy::Swarm => Box::new(Swarm {
+                size: config.swarm_size,
+                ratio: config.swarm_ratio,
+            }),
+        }
+    }
+}
+
+// Moons not owned by `owner` with how many ships defend them, or will soon.
+fn targets<'a>(
+    snapshot: &'a Snapshot,
+    owner: &'a OwnedBy,
+) -> impl Iterator<Item = (&'a MoonView, usize)> {
+    snapshot
+        .moons
+        .iter()
+        .filter(move |moon| moon.owner != *owner)
+        .map(move |moon| {
+            let defenders = moon.ships_against(owner)
+                + snapshot.incoming_against(moon.entity, owner)
+                - snapshot
+                    .incoming(moon.entity, owner)
+                    .min(moon.ships_against(owner));
+            (moon, defenders)
+        })
+}
+
+struct Pace {
+    every: f32,
+    next: f32,
+}
+
+impl Pace {
+    fn new(every: f32) -> Self {
+        Pace { every, next: every }
+    }
+
+    fn ready(&mut self, snapshot: &Snapshot) -> bool {
+        if snapshot.elapsed < self.next {
+            return false;
+        }
+        self.next = snapshot.elapsed + self.every;
+        true
+    }
+}
+
+pub struct Turtle {
+    pace: Pace,
+}
+
+impl Default for Turtle {
+    fn default() -> Self {
+        Turtle {
+            pace: Pace::new(3.),
+        }
+    }
+}
+
+impl AiController for Turtle {
+    fn think(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Vec<AiOrder> {
+        if !self.pace.ready(snapshot) {
+            return vec![];
+        }
+        let mut orders = vec![];
+        let threatened = snapshot
+            .moons_of(owner)
+            .filter(|moon| {
+                moon.ships_against(owner) + snapshot.incoming_against(moon.entity, owner)
+                    > moon.ships_of(owner)
+            })
+            .map(|moon| moon.entity)
+            .collect::<Vec<_>>();
+        for moon in snapshot.moons_of(owner) {
+            let available = moon.ships_of(owner);
+            if threatened.contains(&moon.entity) || moon.ships_against(owner) > 0 {
+                continue;
+            }
+            if let Some(threatened) = threatened.first() {
+                if available > 10 {
+                    orders.push(AiOrder {
+                        from: moon.entity,
+                        towards: *threatened,
+                        ratio: Ratio::Half,
+                    });
+                }
+                continue;
+            }
+            if available < 30 {
+                continue;
+            }
+            let ratio = Ratio::Half;
+            if let Some((target, _)) = targets(snapshot, owner)
+                .filter(|(_, defenders)| ratio.of(available) > defenders * 3)
+                .min_by_key(|(_, defenders)| *defenders + rng.gen_range(0, 3))
+            {
+                orders.push(AiOrder {
+                    from: moon.entity,
+                    towards: target.entity,
+                    ratio,
+                });
+            }
+        }
+        orders
+    }
+
+    // fleets reinforce the weakest moon of the side
+    fn fleet_target(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Option<Entity> {
+        snapshot
+            .moons_of(owner)
+            .min_by_key(|moon| {
+                (moon.ships_of(owner) + snapshot.incoming(moon.entity, owner)) as i32
+                    - (moon.ships_against(owner) + snapshot.incoming_against(moon.entity, owner))
+                        as i32
+            })
+            .map(|moon| moon.entity)
+            .or_else(|| snapshot.moons.iter().choose(rng).map(|moon| moon.entity))
+    }
+}
+
+pub struct Rush {
+    pace: Pace,
+}
+
+impl Default for Rush {
+    fn default() -> Self {
+        Rush {
+            pace: Pace::new(1.),
+        }
+    }
+}
+
+impl AiController for Rush {
+    fn think(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Vec<AiOrder> {
+        if !self.pace.ready(snapshot) {
+            return vec![];
+        }
+        let mut orders = vec![];
+        for moon in snapshot.moons_of(owner) {
+            let available = moon.ships_of(owner);
+            if available < 5 {
+                continue;
+            }
+            // the player is the one to beat, then whoever is closest to fall
+            if let Some((target, _)) = targets(snapshot, owner)
+                .filter(|(_, defenders)| available > *defenders)
+                .min_by_key(|(target, defenders)| {
+                    let is_player =
+                        target.owner == OwnedBy::Player(0) && *owner != OwnedBy::Player(0);
+                    *defenders / if is_player { 2 } else { 1 } + rng.gen_range(0, 3)
+                })
+            {
+                orders.push(AiOrder {
+                    from: moon.entity,
+                    towards: target.entity,
+                    ratio: Ratio::All,
+                });
+            }
+        }
+        orders
+    }
+
+    // a fleet is never held back while the player has moons to take
+    fn launch_fleet(
+        &mut self,
+        _owner: &OwnedBy,
+        snapshot: &Snapshot,
+        chance: f32,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        snapshot.moons_of(&OwnedBy::Player(0)).next().is_some() || rng.gen_bool(chance as f64)
+    }
+
+    fn fleet_target(
+        &mut self,
+        _owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Option<Entity> {
+        snapshot
+            .moons_of(&OwnedBy::Player(0))
+            .choose(rng)
+            .or_else(|| snapshot.moons.iter().choose(rng))
+            .map(|moon| moon.entity)
+    }
+}
+
+const MIN_SHIPS_TO_ATTACK: usize = 8;
+
+pub struct Opportunist {
+    pace: Pace,
+}
+
+impl Default for Opportunist {
+    fn default() -> Self {
+        Opportunist {
+            pace: Pace::new(1.5),
+        }
+    }
+}
+
+impl AiController for Opportunist {
+    fn think(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Vec<AiOrder> {
+        if !self.pace.ready(snapshot) {
+            return vec![];
+        }
+        let mut orders = vec![];
+        for moon in snapshot.moons_of(owner) {
+            let available = moon.ships_of(owner);
+            if available < MIN_SHIPS_TO_ATTACK || moon.ships_against(owner) > 0 {
+                continue;
+            }
+            let ratio = Ratio::ThreeQuarter;
+            if let Some((target, _)) = targets(snapshot, owner)
+                .filter(|(_, defenders)| ratio.of(available) > defenders + defenders / 5 + 3)
+                .min_by_key(|(target, defenders)| {
+                    // a moon already fought over is cheaper than it looks
+                    let contested = target.ships.len() > 1;
+                    *defenders / if contested { 2 } else { 1 } + rng.gen_range(0, 5)
+                })
+            {
+                orders.push(AiOrder {
+                    from: moon.entity,
+                    towards: target.entity,
+                    ratio,
+                });
+            }
+        }
+        orders
+    }
+
+    // fleets join a fight already going on around a moon
+    fn fleet_target(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        rng: &mut dyn RngCore,
+    ) -> Option<Entity> {
+        snapshot
+            .moons
+            .iter()
+            .filter(|moon| {
+                moon.ships.len() > 1 || snapshot.incoming_against(moon.entity, owner) > 0
+            })
+            .choose(rng)
+            .or_else(|| snapshot.moons.iter().choose(rng))
+            .map(|moon| moon.entity)
+    }
+}
+
+// Ships gathered around any moon are sent at the first moon of the player once there
+// are enough of them.
+pub struct Swarm {
+    pub size: usize,
+    pub ratio: Ratio,
+}
+
+impl AiController for Swarm {
+    fn think(
+        &mut self,
+        owner: &OwnedBy,
+        snapshot: &Snapshot,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<AiOrder> {
+        let target = match snapshot.moons_of(&OwnedBy::Player(0)).next() {
+            Some(target) => target.entity,
+            None => return vec![],
+        };
+        snapshot
+            .moons
+            .iter()
+            .filter(|moon| moon.ships_of(owner) > self.size)
+            .map(|moon| AiOrder {
+                from: moon.entity,
+                towards: target,
+                ratio: self.ratio,
+            })
+            .collect()
+    }
+}
+
+// One controller per computer-controlled side, created the first time the side is seen
+// in a match.
+#[derive(Default)]
+pub struct Controllers {
+    by_owner: Vec<(OwnedBy, Box<dyn AiController>)>,
+}
+
+impl Controllers {
+    pub fn clear(&mut self) {
+        self.by_owner.clear();
+    }
+
+    pub fn controller(
+        &mut self,
+        side: &OwnedBy,
+        config: &crate::Config,
+        map: &super::map::MapDefinition,
+    ) -> Option<&mut Box<dyn AiController>> {
+        if !self.by_owner.iter().any(|(owner, _)| owner == side) {
+            let strategy = strategy_for(side, config, map)?;
+            self.by_owner
+                .push((side.clone(), strategy.controller(config)));
+        }
+        self.by_owner
+            .iter_mut()
+            .find(|(owner, _)| owner == side)
+            .map(|(_, controller)| controller)
+    }
+}
+
+pub fn strategy_for(
+    owner: &OwnedBy,
+    config: &crate::Config,
+    map: &super::map::MapDefinition,
+) -> Option<Strategy> {
+    let from_map = map
+        .factions
+        .iter()
+        .find(|faction| OwnedBy::Player(faction.player) == *owner)
+        .map(|faction| faction.strategy);
+    match owner {
+        OwnedBy::Neutral => map.planet_strategy.or(Some(config.planet_strategy)),
+        OwnedBy::Player(0) => from_map,
+        OwnedBy::Player(_) => from_map.or(Some(config.rival_strategy)),
+    }
+}
+
+pub fn snapshot(
+    clock: &crate::clock::GameClock,
+    game: &Game,
+    moons: &Query<(Entity, &Moon, &OwnedBy)>,
+    in_transit: &Query<(&crate::space::MoveTowards, &OwnedBy, &crate::space::Ship)>,
+    planets: &Query<Entity, With<Planet>>,
+    shields: &Query<&Parent, With<crate::space::Shielded>>,
+) -> Snapshot {
+    Snapshot {
+        elapsed: clock.seconds_since_start(),
+        moons: moons
+            .iter()
+            .map(|(entity, moon, owner)| MoonView {
+                entity,
+                index: moon.index,
+                owner: owner.clone(),
+                ships: game.ship_counts.get(&entity).cloned().unwrap_or_default(),
+            })
+            .collect(),
+        in_transit: in_transit
+            .iter()
+            .map(|(moving, owner, ship)| ShipInTransit {
+                owner: owner.clone(),
+                from: moving.from,
+                towards: moving.towards,
+                hit_points: ship.hit_points,
+            })
+            .collect(),
+        planet: planets.iter().next().map(|planet| PlanetView {
+            entity: planet,
+            shielded: game.neutral_moons != 0 || shields.iter().any(|parent| parent.0 == planet),
+        }),
+    }
+}
+
+pub fn ai_orders(
+    game_screen: Res<crate::GameScreen>,
+    screen: Res<super::Screen>,
+    (clock, mut rng, game): (
+        Res<crate::clock::GameClock>,
+        ResMut<crate::rng::MatchRng>,
+        Res<Game>,
+    ),
+    (config, map, mut controllers): (
+        Res<crate::Config>,
+        Res<super::map::MapDefinition>,
+        ResMut<Controllers>,
+    ),
+    (mut orders, replay): (ResMut<Events<Order>>, Res<crate::replay::ReplayState>),
+    moons: Query<(Entity, &Moon, &OwnedBy)>,
+    in_transit: Query<(&crate::space::MoveTowards, &OwnedBy, &crate::space::Ship)>,
+    (planets, shields): (
+        Query<Entity, With<Planet>>,
+        Query<&Parent, With<crate::space::Shielded>>,
+    ),
+) {
+    if game_screen.current_screen != super::CURRENT_SCREEN {
+        return;
+    }
+    if !screen.loaded {
+        controllers.clear();
+        return;
+    }
+    if clock.is_paused() {
+        return;
+    }
+
+    let mut sides = moons
+        .iter()
+        .map(|(_, _, owner)| owner.clone())
+        .chain(std::iter::once(OwnedBy::Neutral))
+        .collect::<Vec<_>>();
+    sides.sort_by_key(|side| match side {
+        OwnedBy::Neutral => 0,
+        OwnedBy::Player(n) => n + 1,
+    });
+    sides.dedup();
+
+    let snapshot = snapshot(&clock, &game, &moons, &in_transit, &planets, &shields);
+    for side in sides {
+        // the orders of the player are recorded, even when the computer plays for them
+        if side == OwnedBy::Player(0) && replay.is_playback() {
+            continue;
+        }
+        let controller = match controllers.controller(&side, &config, &map) {
+            Some(controller) => controller,
+            None => continue,
+        };
+        for order in controller.think(&side, &snapshot, &mut *rng) {
+            orders.send(Order::SendShips {
+                owner: side.clone(),
+                from: order.from,
+                towards: order.towards,
+                ratio: order.ratio,
+            });
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::{camera::Camera, render_graph::base::camera::CAMERA_2D},
+};
+
+use crate::input::Action;
+
+// scale of the camera, below 1 is zoomed in
+const MIN_SCALE: f32 = 0.4;
+const MAX_SCALE: f32 = 2.;
+const WHEEL_ZOOM: f32 = 0.1;
+// zoom by second while a zoom action is held
+const ACTION_ZOOM: f32 = 1.;
+// how far from the planet the camera can go
+const PAN_LIMIT: f32 = 1000.;
+
+pub fn is_world_camera(camera: &Camera) -> bool {
+    camera.name.as_deref() == Some(CAMERA_2D)
+}
+
+// `position` is in window coordinates, as given by `CursorMoved`
+pub fn screen_to_world(position: Vec2, window: &Window, camera: &GlobalTransform) -> Vec2 {
+    let centered = position - Vec2::new(window.width() as f32, window.height() as f32) / 2.;
+    let world = camera.compute_matrix() * centered.extend(0.).extend(1.);
+    world.truncate().truncate()
+}
+
+#[derive(Default)]
+pub struct State {
+    cursor_moved_event_reader: EventReader<CursorMoved>,
+    mouse_wheel_event_reader: EventReader<MouseWheel>,
+    cursor_position: Option<Vec2>,
+}
+
+pub fn camera_control(
+    mut state: Local<State>,
+    (game_screen, screen): (Res<crate::GameScreen>, Res<super::Screen>),
+    (actions, time, touches): (Res<crate::input::Actions>, Res<Time>, Res<Touches>),
+    (cursor_moved_events, mouse_wheel_events): (Res<Events<CursorMoved>>, Res<Events<MouseWheel>>),
+    mut cameras: Query<(&Camera, &mut Transform)>,
+) {
+    let mut pan = Vec2::zero();
+    for cursor_moved in state.cursor_moved_event_reader.iter(&cursor_moved_events) {
+        if let Some(previous) = state.cursor_position {
+            pan += cursor_moved.position - previous;
+        }
+        state.cursor_position = Some(cursor_moved.position);
+    }
+    if !actions.pressed(Action::Pan) {
+        pan = Vec2::zero();
+    }
+
+    let mut zoom = 1.;
+    for wheel in state.mouse_wheel_event_reader.iter(&mouse_wheel_events) {
+        let lines = match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / 20.,
+        };
+        zoom *= 1. - lines * WHEEL_ZOOM;
+    }
+    if actions.pressed(Action::ZoomIn) {
+        zoom *= 1. - ACTION_ZOOM * time.delta_seconds();
+    }
+    if actions.pressed(Action::ZoomOut) {
+        zoom *= 1. + ACTION_ZOOM * time.delta_seconds();
+    }
+
+    // two fingers pinch to zoom and move together to pan
+    let fingers = touches.iter().collect::<Vec<_>>();
+    if let [first, second] = fingers.as_slice() {
+        let before = first.previous_position.distance(second.previous_position);
+        let now = first.position.distance(second.position);
+        if now > 0. {
+            zoom *= before / now;
+        }
+        pan +=
+            (first.position + second.position - first.previous_position - second.previous_position)
+                / 2.;
+    }
+
+    for (camera, mut transform) in cameras.iter_mut() {
+        if !is_world_camera(camera) {
+            continue;
+        }
+        if game_screen.current_screen != super::CURRENT_SCREEN || !screen.loaded {
+            // other screens, and each new match, start from the default view
+            transform.translation.x = 0.;
+            transform.translation.y = 0.;
+            transform.scale = Vec3::one();
+            continue;
+        }
+        let scale = (transform.scale.x * zoom).max(MIN_SCALE).min(MAX_SCALE);
+        transform.scale = Vec3::new(scale, scale, 1.);
+        // the world follows the cursor while dragging
+        transform.translation.x = (transform.translation.x - pan.x * scale)
+            .max(-PAN_LIMIT)
+            .min(PAN_LIMIT);
+        transform.translation.y = (transform.translation.y - pan.y * scale)
+            .max(-PAN_LIMIT)
+            .min(PAN_LIMIT);
+    }
+}
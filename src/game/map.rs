@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ai::Strategy, OwnedBy};
+use crate::space::{RotationDirection, SpawnShipType};
+
+// Layout of a match. Everything left out is picked at random when the match starts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapDefinition {
+    pub planet: PlanetDefinition,
+    pub moons: Vec<MoonDefinition>,
+    // moon the player starts on, counting from 1 like the moon names
+    pub player_start: Option<usize>,
+    // strategies of the computer-controlled factions, the player included to watch them play
+    #[serde(default)]
+    pub factions: Vec<FactionDefinition>,
+    // strategy of the planet, `planet_strategy` from the config when left out
+    #[serde(default)]
+    pub planet_strategy: Option<Strategy>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FactionDefinition {
+    pub player: usize,
+    pub strategy: Strategy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlanetDefinition {
+    pub x: f32,
+    pub y: f32,
+    pub kind: Option<usize>,
+    pub name: Option<String>,
+    pub rotation: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MoonDefinition {
+    pub orbit: f32,
+    // up to this much is randomly added to the orbit radius
+    #[serde(default)]
+    pub orbit_variation: f32,
+    pub speed: Option<f32>,
+    pub direction: Option<RotationDirection>,
+    pub rotation: Option<f32>,
+    pub owner: Option<OwnedBy>,
+    pub spawn: Option<SpawnShipType>,
+}
+
+impl MoonDefinition {
+    pub fn orbiting_at(orbit: f32) -> Self {
+        MoonDefinition {
+            orbit,
+            orbit_variation: 0.,
+            speed: None,
+            direction: None,
+            rotation: None,
+            owner: None,
+            spawn: None,
+        }
+    }
+}
+
+impl Default for MapDefinition {
+    fn default() -> Self {
+        let nb_moon = 3;
+        MapDefinition {
+            planet: PlanetDefinition {
+                x: -200.,
+                y: 0.,
+                kind: None,
+                name: None,
+                rotation: None,
+            },
+            moons: (0..nb_moon)
+                .map(|i| MoonDefinition {
+                    orbit_variation: 30.,
+                    ..MoonDefinition::orbiting_at((i as f32 + 1.) * (300. / nb_moon as f32))
+                })
+                .collect(),
+            player_start: None,
+            factions: vec![],
+            planet_strategy: None,
+        }
+    }
+}
+
+impl MapDefinition {
+    pub fn load(path: &str) -> Result<MapDefinition, Box<dyn std::error::Error>> {
+        let map: MapDefinition = config::read_file(path)?;
+        map.check()?;
+        Ok(map)
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.moons.is_empty() {
+            return Err("a map needs at least one moon".to_string());
+        }
+        if let Some(start) = self.player_start {
+            if start == 0 || start > self.moons.len() {
+                return Err(format!(
+                    "player_start {} is not one of the {} moons",
+                    start,
+                    self.moons.len()
+                ));
+            }
+        }
+        if let Some(kind) = self.planet.kind {
+            if kind >= super::PLANET_SIZES.len() {
+                return Err(format!("unknown planet kind {}", kind));
+            }
+        }
+        Ok(())
+    }
+}
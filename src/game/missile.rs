@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use rand::prelude::IteratorRandom;
+
+use super::{Moon, OwnedBy, Planet, ScreenTag};
+use crate::space::{Orbiter, Ship, ShipExploded};
+
+const MISSILE_SPEED: f32 = 6000.;
+const MISSILE_DAMAGE: i32 = 2;
+// ships hit around a moon when a missile reaches it
+const MISSILE_BLAST: usize = 3;
+const MISSILE_LIFETIME: f32 = 8.;
+
+// Fires missiles from a moon at enemy ships in range, or from the planet at the moons of
+// the player.
+pub struct Launcher {
+    pub timer: Timer,
+}
+
+pub struct Missile {
+    pub owner: OwnedBy,
+    pub target: Entity,
+    pub damage: i32,
+    pub lifetime: Timer,
+}
+
+pub fn arm_launchers(
+    commands: &mut Commands,
+    config: Res<crate::Config>,
+    // a resumed match comes with its launchers
+    moons: Query<Entity, (Added<Moon>, Without<Launcher>)>,
+    planets: Query<Entity, (Added<Planet>, Without<Launcher>)>,
+) {
+    for moon in moons.iter() {
+        commands.insert_one(
+            moon,
+            Launcher {
+                timer: Timer::from_seconds(config.missile_timer, true),
+            },
+        );
+    }
+    for planet in planets.iter() {
+        commands.insert_one(
+            planet,
+            Launcher {
+                timer: Timer::from_seconds(config.planet_missile_timer, true),
+            },
+        );
+    }
+}
+
+pub fn spawn_missile(
+    commands: &mut Commands,
+    mut translation: Vec3,
+    owner: OwnedBy,
+    target: Entity,
+    lifetime: Timer,
+) {
+    translation.z = crate::Z_SHIP;
+    commands.spawn((
+        Transform {
+            translation,
+            scale: Vec3::splat(0.15),
+            ..Default::default()
+        },
+        GlobalTransform::default(),
+    ));
+    let entity = commands.current_entity().unwrap();
+    commands
+        .with(
+            bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
+                .translation(translation.x, translation.y)
+                .user_data(entity.to_bits() as u128),
+        )
+        .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(3.).sensor(true))
+        .with(Missile {
+            owner,
+            target,
+            damage: MISSILE_DAMAGE,
+            lifetime,
+        })
+        .with(ScreenTag);
+}
+
+pub fn fire_missiles(
+    commands: &mut Commands,
+    clock: Res<crate::clock::GameClock>,
+    mut rng: ResMut<crate::rng::MatchRng>,
+    config: Res<crate::Config>,
+    mut launchers: Query<(&mut Launcher, &OwnedBy, &GlobalTransform, Option<&Moon>)>,
+    ships: Query<(Entity, &OwnedBy, &GlobalTransform), With<Ship>>,
+    moons: Query<(Entity, &OwnedBy), With<Moon>>,
+) {
+    for (mut launcher, owner, gt, moon) in launchers.iter_mut() {
+        launcher.timer.tick(clock.delta_seconds());
+        if !launcher.timer.just_finished() {
+            continue;
+        }
+        let target = match (moon, owner) {
+            (Some(_), OwnedBy::Player(_)) => ships
+                .iter()
+                .filter(|(_, ship_owner, _)| *ship_owner != owner)
+                .map(|(ship, _, ship_gt)| (ship, ship_gt.translation.distance(gt.translation)))
+                .filter(|(_, distance)| *distance < config.missile_range)
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                .map(|(ship, _)| ship),
+            (None, _) => moons
+                .iter()
+                .filter(|(_, moon_owner)| **moon_owner == OwnedBy::Player(0))
+                .choose(&mut *rng)
+                .map(|(moon, _)| moon),
+            _ => None,
+        };
+        if let Some(target) = target {
+            spawn_missile(
+                commands,
+                gt.translation,
+                owner.clone(),
+                target,
+                Timer::from_seconds(MISSILE_LIFETIME, false),
+            );
+        }
+    }
+}
+
+pub fn guide_missiles(
+    commands: &mut Commands,
+    clock: Res<crate::clock::GameClock>,
+    mut bodies: ResMut<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
+    (mut game_events, mut explosions): (
+        ResMut<Events<super::GameEvents>>,
+        ResMut<Events<ShipExploded>>,
+    ),
+    mut missiles: Query<(
+        Entity,
+        &mut Missile,
+        &bevy_rapier2d::physics::RigidBodyHandleComponent,
+        &GlobalTransform,
+    )>,
+    targets: Query<&GlobalTransform>,
+    (ships, orbiting): (
+        Query<Entity, With<Ship>>,
+        Query<(Entity, &Orbiter, &OwnedBy), With<Ship>>,
+    ),
+) {
+    for (entity, mut missile, rigid_body, gt) in missiles.iter_mut() {
+        missile.lifetime.tick(clock.delta_seconds());
+        let target = match targets.get(missile.target) {
+            Ok(target) if !missile.lifetime.finished() => target,
+            // the target was destroyed before the missile reached it
+            _ => {
+                commands.despawn_recursive(entity);
+                explosions.send(ShipExploded(*gt));
+                continue;
+            }
+        };
+
+        if target.translation.distance(gt.translation) < 15. {
+            if ships.get(missile.target).is_ok() {
+                game_events.send(super::GameEvents::ShipDamaged(
+                    missile.target,
+                    missile.damage,
+                    Some(missile.owner.clone()),
+                ));
+                commands.despawn_recursive(entity);
+                explosions.send(ShipExploded(*gt));
+                continue;
+            }
+            // a moon, the ships around it are hit
+            orbiting
+                .iter()
+                .filter(|(_, orbiter, owner)| {
+                    orbiter.around == missile.target && **owner != missile.owner
+                })
+                .take(MISSILE_BLAST)
+                .for_each(|(ship, _, _)| {
+                    game_events.send(super::GameEvents::ShipDamaged(
+                        ship,
+                        missile.damage,
+                        Some(missile.owner.clone()),
+                    ))
+                });
+            commands.despawn_recursive(entity);
+            explosions.send(ShipExploded(*gt));
+            continue;
+        }
+
+        let mut body = bodies.get_mut(rigid_body.handle()).unwrap();
+        let (linvel, rot) = crate::space::go_from_to_rapier(
+            bevy_rapier2d::rapier::math::Vector::new(0., 0.),
+            bevy_rapier2d::rapier::math::Vector::new(
+                target.translation.x - gt.translation.x,
+                target.translation.y - gt.translation.y,
+            ),
+        );
+        body.linvel = linvel * MISSILE_SPEED * crate::space::SPEED_FRAME;
+        body.position.rotation =
+            bevy_rapier2d::na::UnitComplex::from_angle(rot - std::f32::consts::FRAC_PI_2);
+    }
+}
@@ -1,9 +1,20 @@
 use bevy::prelude::*;
 use rand::{prelude::IteratorRandom, seq::SliceRandom, Rng};
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::input::Action;
 
 const CURRENT_SCREEN: crate::Screen = crate::Screen::Game;
 
+pub mod adaptive;
+pub mod ai;
+pub mod camera;
+pub mod map;
+pub mod missile;
+pub mod pause;
+pub mod save;
+pub mod stats;
 pub mod ui;
 
 struct ScreenTag;
@@ -11,51 +22,109 @@ struct ScreenTag;
 pub struct Screen {
     loaded: bool,
     first_load: bool,
+    save_on_exit: bool,
 }
 impl Default for Screen {
     fn default() -> Self {
         Screen {
             loaded: false,
             first_load: true,
+            save_on_exit: false,
         }
     }
 }
 
-pub struct Plugin;
-impl bevy::app::Plugin for Plugin {
+// How the next match will be set up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchStart {
+    New,
+    Resume,
+    // left as it is behind the settings opened from the pause menu, until coming back to it
+    Suspended,
+}
+
+impl Default for MatchStart {
+    fn default() -> Self {
+        MatchStart::New
+    }
+}
+
+pub struct SimulationPlugin;
+impl bevy::app::Plugin for SimulationPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_resource(Screen::default())
             .init_resource::<Game>()
+            .init_resource::<MatchStart>()
+            .init_resource::<map::MapDefinition>()
+            .init_resource::<ai::Controllers>()
+            .init_resource::<adaptive::Adaptation>()
+            .init_resource::<stats::GameStats>()
             .add_event::<GameEvents>()
             .add_event::<InterestingEvent>()
-            .add_event::<ui::InteractionEvent>()
+            .add_event::<Order>()
+            .add_system(ship_count)
+            .add_system(setup_game)
+            .add_system(setup_finish)
+            .add_system(change_owner)
+            .add_system(adaptive::adapt)
+            .add_system(planet_defense)
+            .add_system(asteroid_belt)
+            .add_system(asteroid)
+            .add_system(ai::ai_orders)
+            .add_system(missile::arm_launchers)
+            .add_system(missile::fire_missiles)
+            .add_system(missile::guide_missiles)
+            .add_system(self_destruct)
+            .add_system(scoring)
+            .add_system(stats::update_stats)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, execute_orders)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, save::save_match)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<ui::InteractionEvent>()
+            .init_resource::<pause::Pause>()
             .add_system(keyboard_input_system)
-            .add_system(ui::ship_count)
             .add_system(ui::setup)
             .add_system(ui::interaction)
             .add_system(ui::ui_update)
             .add_system(ui::ui_update_on_interaction_event)
+            .add_system(ui::keyboard_control)
+            .add_system(ui::target_control)
+            .add_system(camera::camera_control)
+            .add_system(pause::pause_input)
+            .add_system(pause::pause_overlay)
+            .add_system(pause::display_pause)
             .add_system(ui::orders)
             .add_system(ui::change_ratio_ui)
+            .add_system(ui::change_ship_class)
             .add_system(ui::timer)
             .add_system(ui::scorer)
             .add_system_to_stage(bevy::app::stage::PRE_UPDATE, ui::focus_system)
-            .add_system(setup_game)
-            .add_system(setup_finish)
-            .add_system(change_owner)
-            .add_system(planet_defense)
-            .add_system(asteroid_belt)
-            .add_system(asteroid)
-            .add_system(moon_attack)
-            .add_system(self_destruct)
-            .add_system(scoring)
-            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, ui::change_owner_interacted)
-            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, ui::interaction_box)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, ui::select_start_moon)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, ui::change_owner_interacted);
     }
 }
 
+pub const PLANET_SIZES: &[u32] = &[
+    365, 340, 390, 250, 395, 360, 430, 430, 395, 400, 400, 395, 375, 370, 385, 380, 385, 385, 370,
+    390,
+];
+
 pub struct Planet {
     pub name: String,
+    pub kind: usize,
+}
+
+impl Planet {
+    pub fn radius(&self) -> f32 {
+        PLANET_SIZES[self.kind] as f32 / 10.
+    }
 }
 
 pub struct Moon {
@@ -63,154 +132,227 @@ pub struct Moon {
     pub planet: Entity,
 }
 
+// A planet or a moon, as it can be referenced outside of the running match.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Planet,
+    Moon(i32),
+}
+
+fn spawn_planet(
+    commands: &mut Commands,
+    planet: Planet,
+    position: bevy_rapier2d::na::Isometry2<f32>,
+    angvel: f32,
+    owner: OwnedBy,
+    (fleet, asteroid_belt): (PlanetFleet, AsteroidBelt),
+) -> Entity {
+    let collider_radius = planet.radius() * 9. / 10.;
+    commands.spawn((
+        Transform {
+            scale: Vec3::splat(0.10),
+            translation: Vec3::new(
+                position.translation.x,
+                position.translation.y,
+                crate::Z_PLANET,
+            ),
+            ..Default::default()
+        },
+        GlobalTransform::default(),
+    ));
+    let planet_entity = commands.current_entity().unwrap();
+    commands
+        .with(
+            bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
+                .position(position)
+                .angvel(angvel)
+                .user_data(planet_entity.to_bits() as u128),
+        )
+        .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(collider_radius).sensor(true))
+        .with(planet)
+        .with(owner)
+        .with(fleet)
+        .with(asteroid_belt)
+        .with(ScreenTag);
+    planet_entity
+}
+
+fn spawn_moon(
+    commands: &mut Commands,
+    moon: Moon,
+    owner: OwnedBy,
+    (orbiter, spawner): (crate::space::Orbiter, crate::space::SpawnShip),
+    position: bevy_rapier2d::na::Isometry2<f32>,
+    angvel: f32,
+) -> Entity {
+    commands
+        .spawn((
+            Transform {
+                scale: Vec3::splat(0.10),
+                translation: Vec3::new(
+                    position.translation.x,
+                    position.translation.y,
+                    crate::Z_MOON,
+                ),
+                ..Default::default()
+            },
+            GlobalTransform::default(),
+        ))
+        .with(orbiter)
+        .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(10.).sensor(true))
+        .with(spawner);
+    let entity = commands.current_entity().unwrap();
+    commands
+        .with(
+            bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
+                .angvel(angvel)
+                .position(position)
+                .user_data(entity.to_bits() as u128),
+        )
+        .with(moon)
+        .with(owner)
+        .with(ScreenTag);
+    entity
+}
+
 fn setup_game(
     commands: &mut Commands,
     (game_screen, mut game, screen): (Res<crate::GameScreen>, ResMut<Game>, Res<Screen>),
-    config: Res<crate::Config>,
-    time: Res<Time>,
-    asset_handles: Res<crate::AssetHandles>,
-    mut events: ResMut<Events<ui::InteractionEvent>>,
+    (mut config, mut map, mut match_start): (
+        ResMut<crate::Config>,
+        ResMut<map::MapDefinition>,
+        ResMut<MatchStart>,
+    ),
+    (mut clock, mut rng): (
+        ResMut<crate::clock::GameClock>,
+        ResMut<crate::rng::MatchRng>,
+    ),
+    (mut stats, mut campaign, mut adaptation): (
+        ResMut<stats::GameStats>,
+        ResMut<crate::campaign::Campaign>,
+        ResMut<adaptive::Adaptation>,
+    ),
 ) {
-    game.elapsed += time.delta_seconds();
+    game.elapsed += clock.delta_seconds();
     if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
         info!("Loading screen");
-        game.elapsed = 0.;
-        game.score = 0.;
 
-        let game_handles = asset_handles.get_game_handles_unsafe();
-
-        let planet = game_handles
-            .planets
-            .choose(&mut rand::thread_rng())
-            .unwrap();
+        if *match_start == MatchStart::Resume {
+            *match_start = MatchStart::New;
+            match save::SavedMatch::load(save::SAVE_FILE) {
+                Ok(saved) => {
+                    info!("resuming saved match");
+                    match saved.level() {
+                        Some(level) if level < campaign.levels.len() => {
+                            campaign.start(level, &mut config, &mut map)
+                        }
+                        Some(level) => warn!("saved campaign level {} not found", level + 1),
+                        None => (),
+                    }
+                    saved.restore(commands, &mut game, &mut clock, &mut rng, &mut adaptation);
+                    if let Err(err) = std::fs::remove_file(save::SAVE_FILE) {
+                        warn!("could not remove saved match: {}", err);
+                    }
+                    return;
+                }
+                Err(err) => warn!("could not load saved match: {}", err),
+            }
+        }
 
-        let shift_left = -200.;
+        game.elapsed = 0.;
+        game.score = 0.;
+        game.level = campaign.current_level();
+        *stats = stats::GameStats::default();
+        clock.reset();
+        rng.new_match();
+        info!("seed: {}", rng.seed());
+
+        let planet_kind = map
+            .planet
+            .kind
+            .unwrap_or_else(|| rng.gen_range(0, PLANET_SIZES.len()));
+        let planet_rotation = map
+            .planet
+            .rotation
+            .unwrap_or_else(|| rng.gen_range(-1., 1.) * 0.2);
+        let planet_name = map.planet.name.clone().unwrap_or_else(|| {
+            crate::AssetHandles::get_planet_names()
+                .choose(&mut *rng)
+                .unwrap()
+                .to_string()
+        });
 
-        commands.spawn(SpriteBundle {
-            transform: Transform {
-                scale: Vec3::splat(0.10),
-                translation: Vec3::new(shift_left, 0., crate::Z_PLANET),
-                ..Default::default()
+        let planet = spawn_planet(
+            commands,
+            Planet {
+                name: planet_name,
+                kind: planet_kind,
             },
-            material: planet.0.clone(),
-            ..Default::default()
-        });
-        let planet_entity = commands.current_entity().unwrap();
-        commands
-            .with(
-                bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
-                    .position(bevy_rapier2d::na::Isometry2::translation(shift_left, 0.))
-                    .angvel(rand::thread_rng().gen_range(-1., 1.) * 0.2)
-                    .user_data(planet_entity.to_bits() as u128),
-            )
-            .with(
-                bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(
-                    planet.1 as f32 / 10. * 9. / 10.,
-                )
-                .sensor(true),
-            )
-            .with(Planet {
-                name: asset_handles
-                    .get_planet_names()
-                    .choose(&mut rand::thread_rng())
-                    .unwrap()
-                    .to_string(),
-            })
-            .with(ui::Interaction::None)
-            .with(ui::InteractionBox {
-                radius: planet.1 as f32 / 10. + 5.,
-            })
-            .with(OwnedBy::Neutral)
-            .with(PlanetFleet::new(&config))
-            .with(AsteroidBelt::new(&config))
-            .with(ScreenTag);
-        let planet = commands.current_entity().unwrap();
-
-        let nb_moon = 3; //rand::thread_rng().gen_range(2, 4);
-
-        let player_start_moon = rand::thread_rng().gen_range(0, nb_moon);
-
-        for i in 0..nb_moon {
-            let self_rotation = rand::thread_rng().gen_range(-1., 1.) * std::f32::consts::FRAC_PI_4;
-            let orbiter = crate::space::Orbiter::every(
-                rand::thread_rng().gen_range(0.01, 0.05),
-                planet,
-                if rand::thread_rng().gen_bool(0.5) {
+            bevy_rapier2d::na::Isometry2::translation(map.planet.x, map.planet.y),
+            planet_rotation,
+            OwnedBy::Neutral,
+            (PlanetFleet::new(&config), AsteroidBelt::new(&config)),
+        );
+
+        let player_start_moon = map
+            .player_start
+            .map(|start| start - 1)
+            .unwrap_or_else(|| rng.gen_range(0, map.moons.len()));
+
+        for (i, moon) in map.moons.iter().enumerate() {
+            let self_rotation = moon
+                .rotation
+                .unwrap_or_else(|| rng.gen_range(-1., 1.) * std::f32::consts::FRAC_PI_4);
+            let speed = moon.speed.unwrap_or_else(|| rng.gen_range(0.01, 0.05));
+            let direction = moon.direction.unwrap_or_else(|| {
+                if rng.gen_bool(0.5) {
                     crate::space::RotationDirection::Clockwise
                 } else {
                     crate::space::RotationDirection::CounterClockwise
-                },
-                (i as f32 + 1.) * (300. / nb_moon as f32) + rand::thread_rng().gen_range(0., 30.),
-            )
-            .self_rotate();
-            let start_position = crate::space::target_orbiting_position(
-                time.seconds_since_startup() as f32,
-                &orbiter,
-            );
+                }
+            });
+            let orbit = if moon.orbit_variation > 0. {
+                moon.orbit + rng.gen_range(0., moon.orbit_variation)
+            } else {
+                moon.orbit
+            };
+            let orbiter = crate::space::Orbiter::every(speed, planet, direction, orbit, &mut *rng)
+                .self_rotate();
+            let start_position =
+                crate::space::target_orbiting_position(clock.seconds_since_start(), &orbiter);
 
-            commands
-                .spawn(SpriteBundle {
-                    transform: Transform {
-                        scale: Vec3::splat(0.10),
-                        translation: Vec3::new(
-                            start_position.x + shift_left,
-                            start_position.y,
-                            crate::Z_MOON,
-                        ),
-                        ..Default::default()
-                    },
-                    material: game_handles
-                        .orbiters
-                        .choose(&mut rand::thread_rng())
-                        .unwrap()
-                        .clone_weak(),
-                    ..Default::default()
-                })
-                .with(orbiter)
-                .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(10.).sensor(true));
             let rot = if self_rotation < 0. {
                 crate::space::RotationDirection::Clockwise
             } else {
                 crate::space::RotationDirection::CounterClockwise
             };
-            if player_start_moon == i {
-                let mut spawner = crate::space::SpawnShipType::Basic.to_components(rot);
-                spawner.every.set_elapsed(spawner.every.duration() / 2.);
-                commands.with(spawner);
+            let owner = if player_start_moon == i {
+                OwnedBy::Player(0)
             } else {
-                commands.with(crate::space::SpawnShipType::Neutral.to_components(rot));
-            }
-            let entity = commands.current_entity().unwrap();
-            commands.with(
-                bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
-                    .angvel(self_rotation)
-                    .position(bevy_rapier2d::na::Isometry2::translation(
-                        start_position.x + shift_left,
-                        start_position.y,
-                    ))
-                    .user_data(entity.to_bits() as u128),
-            );
-            commands
-                .with_children(|p| {
-                    p.spawn((crate::space::SpawnShipProgress,));
-                })
-                .with(ui::Interaction::None)
-                .with(ui::InteractionBox { radius: 30. })
-                .with(Moon {
-                    index: i + 1,
+                moon.owner.clone().unwrap_or(OwnedBy::Neutral)
+            };
+            let spawn_type = moon.spawn.unwrap_or(match owner {
+                OwnedBy::Neutral => crate::space::SpawnShipType::Neutral,
+                OwnedBy::Player(_) => crate::space::SpawnShipType::Basic,
+            });
+            let spawner = match owner {
+                OwnedBy::Player(_) => spawn_type.to_components(rot).with_headstart(0.5),
+                OwnedBy::Neutral => spawn_type.to_components(rot),
+            };
+            spawn_moon(
+                commands,
+                Moon {
+                    index: i as i32 + 1,
                     planet,
-                })
-                .with(if player_start_moon == i {
-                    OwnedBy::Player(0)
-                } else {
-                    OwnedBy::Neutral
-                })
-                .with(ScreenTag);
-            if player_start_moon == i {
-                events.send(ui::InteractionEvent::Clicked(Some(
-                    commands.current_entity().unwrap(),
-                )));
-            }
+                },
+                owner,
+                (orbiter, spawner),
+                bevy_rapier2d::na::Isometry2::translation(
+                    start_position.x + map.planet.x,
+                    start_position.y + map.planet.y,
+                ),
+                self_rotation,
+            );
         }
     }
 }
@@ -230,10 +372,15 @@ fn tear_down(
     commands: &mut Commands,
     game_screen: Res<crate::GameScreen>,
     mut screen: ResMut<Screen>,
+    mut clock: ResMut<crate::clock::GameClock>,
+    match_start: Res<MatchStart>,
     query: Query<Entity, With<ScreenTag>>,
     ship_query: Query<Entity, With<crate::space::Ship>>,
 ) {
-    if game_screen.current_screen != CURRENT_SCREEN && screen.loaded {
+    if game_screen.current_screen != CURRENT_SCREEN
+        && screen.loaded
+        && *match_start != MatchStart::Suspended
+    {
         info!("tear down");
 
         for entity in ship_query.iter() {
@@ -244,32 +391,29 @@ fn tear_down(
             commands.despawn_recursive(entity);
         }
 
+        // other screens use the clock too
+        clock.resume();
+        clock.set_speed(1.);
+
         screen.loaded = false;
     }
 }
 
 fn keyboard_input_system(
     mut game_screen: ResMut<crate::GameScreen>,
-    screen: ResMut<Screen>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut screen: ResMut<Screen>,
+    actions: Res<crate::input::Actions>,
+    mut settings: ResMut<crate::Settings>,
     mut wnds: ResMut<Windows>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN && screen.loaded {
-        if keyboard_input.just_released(KeyCode::Escape) {
-            game_screen.current_screen = crate::Screen::Menu;
-        } else if keyboard_input.just_released(KeyCode::F) {
-            let window = wnds.get_primary_mut().unwrap();
-            match window.mode() {
-                bevy::window::WindowMode::Windowed => {
-                    window.set_mode(bevy::window::WindowMode::BorderlessFullscreen)
-                }
-                _ => window.set_mode(bevy::window::WindowMode::Windowed),
-            }
+        if actions.just_released(Action::ToggleFullscreen) {
+            crate::settings::toggle_fullscreen(&mut settings, &mut wnds);
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OwnedBy {
     Neutral,
     Player(usize),
@@ -286,7 +430,7 @@ impl Default for Player {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum GameState {
     Play,
     Win,
@@ -309,9 +453,11 @@ pub struct Game {
     pub elapsed: f32,
     pub ship_counts: std::collections::HashMap<Entity, std::collections::HashMap<OwnedBy, usize>>,
     pub neutral_moons: usize,
+    // campaign level being played, kept for the saved match
+    pub level: Option<usize>,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Ratio {
     ThreeQuarter,
     Half,
@@ -346,6 +492,15 @@ impl Ratio {
         }
     }
 
+    pub fn previous(&mut self) {
+        *self = match self {
+            Ratio::ThreeQuarter => Ratio::All,
+            Ratio::Half => Ratio::ThreeQuarter,
+            Ratio::OneQuarter => Ratio::Half,
+            Ratio::All => Ratio::OneQuarter,
+        }
+    }
+
     pub fn as_usize(&self) -> usize {
         match self {
             Ratio::All => 4,
@@ -380,13 +535,111 @@ impl Ratio {
 
 #[derive(PartialEq)]
 pub enum GameEvents {
-    ShipDamaged(Entity, i32),
+    // the owner of what hit the ship, `None` for asteroids, the planet and self destruction
+    ShipDamaged(Entity, i32, Option<OwnedBy>),
     PlanetShield(Entity, f32),
     MoonConquered(Entity, OwnedBy),
     PlanetConquered(Entity),
 }
 
-pub enum InterestingEvent {}
+// What happened during the match, for the stats shown at its end.
+pub enum InterestingEvent {
+    ShipBuilt(OwnedBy),
+    ShipDestroyed { owner: OwnedBy, by: Option<OwnedBy> },
+    MoonCaptured { from: OwnedBy, to: OwnedBy },
+    FleetSent { owner: OwnedBy, size: usize },
+    // ships sent by the planet towards the moons
+    FleetWave(usize),
+    AsteroidSurvived,
+    // the planet can be conquered
+    ShieldDropped,
+}
+
+// Orders are sent during `UPDATE` and executed in `POST_UPDATE` of the same frame, whatever
+// issued them. Only the ships of `owner` orbiting `from` are sent, whoever owns the moon.
+#[derive(Clone, Debug)]
+pub enum Order {
+    SendShips {
+        owner: OwnedBy,
+        from: Entity,
+        towards: Entity,
+        ratio: Ratio,
+    },
+    SwitchShipClass {
+        owner: OwnedBy,
+        moon: Entity,
+        class: crate::space::SpawnShipType,
+    },
+}
+
+fn execute_orders(
+    commands: &mut Commands,
+    game: Res<Game>,
+    mut interesting_events: ResMut<Events<InterestingEvent>>,
+    (mut event_reader, events): (Local<EventReader<Order>>, Res<Events<Order>>),
+    query_ships: Query<(
+        Entity,
+        &crate::space::Orbiter,
+        &OwnedBy,
+        &crate::space::Ship,
+    )>,
+    mut query_spawners: Query<(&mut crate::space::SpawnShip, &OwnedBy), With<Moon>>,
+) {
+    for order in event_reader.iter(&events) {
+        match order {
+            Order::SendShips {
+                owner,
+                from,
+                towards,
+                ratio,
+            } => {
+                let ship_count = game
+                    .ship_counts
+                    .get(from)
+                    .and_then(|counts| counts.get(owner))
+                    .copied()
+                    .unwrap_or(0);
+
+                let mut sent = 0;
+                query_ships
+                    .iter()
+                    .filter(|(_, orbiter, owned_by, _)| {
+                        orbiter.around == *from && *owned_by == owner
+                    })
+                    .take(ratio.of(ship_count))
+                    .for_each(|(entity, _, _, ship)| {
+                        sent += 1;
+                        commands.remove_one::<crate::space::Orbiter>(entity);
+                        commands.insert_one(
+                            entity,
+                            crate::space::MoveTowards {
+                                speed: ship.speed
+                                    * match owner {
+                                        OwnedBy::Neutral => 2000.,
+                                        OwnedBy::Player(_) => 2500.,
+                                    },
+                                from: *from,
+                                towards: *towards,
+                            },
+                        );
+                    });
+                if sent > 0 {
+                    interesting_events.send(InterestingEvent::FleetSent {
+                        owner: owner.clone(),
+                        size: sent,
+                    });
+                }
+            }
+            Order::SwitchShipClass { owner, moon, class } => {
+                if let Ok((mut spawner, moon_owner)) = query_spawners.get_mut(*moon) {
+                    if moon_owner == owner && spawner.kind != *class {
+                        spawner.switch_to(*class);
+                    }
+                }
+            }
+        }
+    }
+}
 
 fn change_owner(
     mut game_events: ResMut<Events<crate::game::GameEvents>>,
@@ -405,6 +658,41 @@ fn change_owner(
     }
 }
 
+pub fn ship_count(
+    mut game: ResMut<Game>,
+    mut events: ResMut<Events<GameEvents>>,
+    query_moon: Query<(Entity, &OwnedBy), With<Moon>>,
+    query_planet: Query<Entity, With<Planet>>,
+    query_ships: Query<(&crate::space::Orbiter, &crate::game::OwnedBy), With<crate::space::Ship>>,
+) {
+    let mut neutral_moons = 0;
+    for (moon_entity, owner) in query_moon.iter() {
+        let ships_orbiting_count = query_ships
+            .iter()
+            .filter(|(orbiter, _)| orbiter.around == moon_entity)
+            .fold(
+                std::collections::HashMap::new(),
+                |mut counts, (_, owned_by)| {
+                    let count = counts.entry(owned_by.clone()).or_insert(0);
+                    *count += 1;
+                    counts
+                },
+            );
+        game.ship_counts.insert(moon_entity, ships_orbiting_count);
+        if let OwnedBy::Neutral = owner {
+            neutral_moons += 1;
+        }
+    }
+    if let Some(planet) = query_planet.iter().next() {
+        game.ship_counts
+            .insert(planet, std::collections::HashMap::new());
+        if neutral_moons == 0 && game.neutral_moons != 0 {
+            events.send(GameEvents::PlanetShield(planet, 10.0))
+        }
+    }
+    game.neutral_moons = neutral_moons;
+}
+
 pub struct PlanetFleet {
     timer: Timer,
     last_happened: f32,
@@ -420,78 +708,103 @@ impl PlanetFleet {
             iteration: 0.,
         }
     }
+
+    // the time since the last fleet is kept
+    pub fn reconfigure(&mut self, config: &crate::Config) {
+        self.timer.set_duration(config.fleet_timer);
+    }
 }
 
+// The fleets coming out of the planet have a budget set by the difficulty, the controller of
+// the planet decides whether one is launched and where its ships go.
 pub fn planet_defense(
     commands: &mut Commands,
-    time: Res<Time>,
-    config: Res<crate::Config>,
+    clock: Res<crate::clock::GameClock>,
+    mut rng: ResMut<crate::rng::MatchRng>,
+    (config, adaptation): (Res<crate::Config>, Res<adaptive::Adaptation>),
     mut game: ResMut<Game>,
-    mut game_screen: ResMut<crate::GameScreen>,
-    asset_handles: Res<crate::AssetHandles>,
+    (mut game_screen, mut interesting_events): (
+        ResMut<crate::GameScreen>,
+        ResMut<Events<InterestingEvent>>,
+    ),
+    (mut controllers, map): (ResMut<ai::Controllers>, Res<map::MapDefinition>),
     mut planet_fleet: Query<(Entity, &GlobalTransform, &mut PlanetFleet)>,
-    moons: Query<(Entity, &OwnedBy), With<Moon>>,
+    moons: Query<(Entity, &Moon, &OwnedBy)>,
+    (in_transit, planets, shields): (
+        Query<(&crate::space::MoveTowards, &OwnedBy, &crate::space::Ship)>,
+        Query<Entity, With<Planet>>,
+        Query<&Parent, With<crate::space::Shielded>>,
+    ),
 ) {
     for (planet, gt, mut fleet) in planet_fleet.iter_mut() {
-        fleet.timer.tick(time.delta_seconds());
-        fleet.last_happened += time.delta_seconds();
+        fleet.timer.tick(clock.delta_seconds());
+        fleet.last_happened += clock.delta_seconds();
         if fleet.timer.just_finished() {
             let mut override_chance = None;
             let mut override_min_health = None;
             let mut override_max_health = None;
             let neutral_moons = moons
                 .iter()
-                .filter(|(_, moon_owner)| **moon_owner == OwnedBy::Neutral)
+                .filter(|(_, _, moon_owner)| **moon_owner == OwnedBy::Neutral)
                 .count();
             if neutral_moons == 0 {
-                override_chance = Some(0.75);
+                override_chance = Some(config.fleet_override_chance);
                 override_min_health = Some(0);
                 override_max_health = Some(4 + (game.elapsed / 60.).ceil() as i32);
             }
 
-            if rand::thread_rng().gen_bool(override_chance.unwrap_or(config.fleet_chance) as f64) {
-                let game_handles = asset_handles.get_game_handles_unsafe();
-
-                let ship = game_handles.ships[2]
-                    .choose(&mut rand::thread_rng())
-                    .unwrap();
+            let controller = match controllers.controller(&OwnedBy::Neutral, &config, &map) {
+                Some(controller) => controller,
+                None => continue,
+            };
+            let snapshot = ai::snapshot(&clock, &game, &moons, &in_transit, &planets, &shields);
+            if controller.launch_fleet(
+                &OwnedBy::Neutral,
+                &snapshot,
+                override_chance.unwrap_or(config.fleet_chance),
+                &mut *rng,
+            ) {
                 let mut translation = gt.translation.clone();
                 translation.z = crate::Z_SHIP;
                 let player_moons = moons
                     .iter()
-                    .filter(|(_, moon_owner)| **moon_owner == OwnedBy::Player(0))
+                    .filter(|(_, _, moon_owner)| **moon_owner == OwnedBy::Player(0))
                     .count();
                 if player_moons == 0 {
                     game.state = GameState::Lose;
                     game_screen.current_screen = crate::Screen::End;
                 }
-                let mut hit_points_to_spawn = ((config.fleet_chance
+                let budget = (config.fleet_chance
                     * (fleet.last_happened / fleet.timer.duration() + fleet.iteration)
-                    / 1.5) as i32
-                    * player_moons as i32)
-                    - 1;
+                    / config.fleet_health_divisor) as i32
+                    * player_moons as i32;
+                let mut hit_points_to_spawn =
+                    (budget as f32 * adaptation.factor(&config)) as i32 - 1;
                 let mut i = -0.2;
                 let mut zero_spawned = 0;
+                let mut wave = 0;
                 while hit_points_to_spawn > 0 {
                     let max_hit_points =
                         override_max_health.unwrap_or(2.max((game.elapsed / 60.).ceil() as i32));
-                    let min_hit_points = override_min_health.unwrap_or(-4)
+                    let min_hit_points = override_min_health.unwrap_or(config.fleet_min_health)
                         + zero_spawned / game.elapsed.ceil() as i32;
                     let spawn_hit_points = 0.max(
-                        rand::thread_rng()
-                            .gen_range((max_hit_points - 1).min(min_hit_points), max_hit_points),
+                        rng.gen_range((max_hit_points - 1).min(min_hit_points), max_hit_points),
                     );
-                    let moon = moons.iter().choose(&mut rand::thread_rng()).unwrap();
+                    let moon =
+                        match controller.fleet_target(&OwnedBy::Neutral, &snapshot, &mut *rng) {
+                            Some(moon) => moon,
+                            None => break,
+                        };
                     let scale = (spawn_hit_points as f32 + 3.) / 4.;
-                    commands.spawn(SpriteBundle {
-                        transform: Transform {
+                    commands.spawn((
+                        Transform {
                             translation,
                             scale: Vec3::splat(0.15 * scale),
                             ..Default::default()
                         },
-                        material: ship.clone(),
-                        ..Default::default()
-                    });
+                        GlobalTransform::default(),
+                    ));
                     let entity = commands.current_entity().unwrap();
                     commands
                         .with(
@@ -506,11 +819,13 @@ pub fn planet_defense(
                         .with(crate::space::MoveTowards {
                             speed: 2000.,
                             from: planet,
-                            towards: moon.0,
+                            towards: moon,
                         })
                         .with(crate::game::OwnedBy::Neutral)
                         .with(crate::space::Ship {
                             hit_points: spawn_hit_points,
+                            speed: 1.,
+                            tier: (spawn_hit_points as usize).min(4),
                         });
                     commands.with(SelfDestruct(Timer::from_seconds(
                         match spawn_hit_points {
@@ -525,6 +840,11 @@ pub fn planet_defense(
                     }
                     hit_points_to_spawn -= spawn_hit_points;
                     i += 0.1;
+                    wave += 1;
+                    interesting_events.send(InterestingEvent::ShipBuilt(OwnedBy::Neutral));
+                }
+                if wave > 0 {
+                    interesting_events.send(InterestingEvent::FleetWave(wave));
                 }
                 fleet.last_happened = 0.;
                 fleet.iteration += 1.;
@@ -535,13 +855,13 @@ pub fn planet_defense(
 
 fn self_destruct(
     mut game_events: ResMut<Events<crate::game::GameEvents>>,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
     mut to_destroys: Query<(Entity, &mut SelfDestruct)>,
 ) {
     for (entity, mut to_destroy) in to_destroys.iter_mut() {
-        to_destroy.0.tick(time.delta_seconds());
+        to_destroy.0.tick(clock.delta_seconds());
         if to_destroy.0.just_finished() {
-            game_events.send(GameEvents::ShipDamaged(entity, 500));
+            game_events.send(GameEvents::ShipDamaged(entity, 500, None));
         }
     }
 }
@@ -557,31 +877,32 @@ impl AsteroidBelt {
             timer: Timer::from_seconds(config.asteroid_timer, true),
         }
     }
+
+    pub fn reconfigure(&mut self, config: &crate::Config) {
+        self.timer.set_duration(config.asteroid_timer);
+    }
 }
 
 pub fn asteroid_belt(
     commands: &mut Commands,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
+    mut rng: ResMut<crate::rng::MatchRng>,
     game: Res<Game>,
-    config: Res<crate::Config>,
-    asset_handles: Res<crate::AssetHandles>,
+    (config, adaptation): (Res<crate::Config>, Res<adaptive::Adaptation>),
     mut asteroids: Query<&mut AsteroidBelt>,
     moons: Query<&GlobalTransform, With<Moon>>,
 ) {
     for mut asteroid in asteroids.iter_mut() {
-        asteroid.timer.tick(time.delta_seconds());
+        asteroid
+            .timer
+            .tick(clock.delta_seconds() * adaptation.factor(&config));
         if asteroid.timer.just_finished() {
-            if rand::thread_rng().gen_bool(config.asteroid_chance as f64) {
-                let game_handles = asset_handles.get_game_handles_unsafe();
-                let meteor = game_handles
-                    .meteors
-                    .choose(&mut rand::thread_rng())
-                    .unwrap();
-                let (start_x, start_y) = match rand::thread_rng().gen_range(0, 5) {
-                    0 => (-700., rand::thread_rng().gen_range(-400., 400.)),
-                    1 => (700., rand::thread_rng().gen_range(-400., 400.)),
-                    2 => (rand::thread_rng().gen_range(-700., 700.), -400.),
-                    _ => (rand::thread_rng().gen_range(-700., 700.), 400.),
+            if rng.gen_bool(config.asteroid_chance as f64) {
+                let (start_x, start_y) = match rng.gen_range(0, 5) {
+                    0 => (-700., rng.gen_range(-400., 400.)),
+                    1 => (700., rng.gen_range(-400., 400.)),
+                    2 => (rng.gen_range(-700., 700.), -400.),
+                    _ => (rng.gen_range(-700., 700.), 400.),
                 };
 
                 let translation = Vec3::new(start_x, start_y, crate::Z_SHIP);
@@ -595,30 +916,29 @@ pub fn asteroid_belt(
                     .0;
                 let target = moons
                     .get(*target)
-                    .unwrap_or_else(|_| moons.iter().choose(&mut rand::thread_rng()).unwrap());
+                    .unwrap_or_else(|_| moons.iter().choose(&mut *rng).unwrap());
 
-                commands.spawn(SpriteBundle {
-                    transform: Transform {
+                commands.spawn((
+                    Transform {
                         translation,
                         scale: Vec3::splat(0.4),
                         ..Default::default()
                     },
-                    material: meteor.clone(),
-                    ..Default::default()
-                });
+                    GlobalTransform::default(),
+                ));
                 let entity = commands.current_entity().unwrap();
                 let a = bevy_rapier2d::rapier::math::Vector::new(
                     -start_x + target.translation.x,
                     -start_y + target.translation.y,
                 )
                 .normalize()
-                    * rand::thread_rng().gen_range(190., 260.);
+                    * rng.gen_range(190., 260.);
                 commands
                     .with(
                         bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
                             .translation(translation.x, translation.y)
                             .user_data(entity.to_bits() as u128)
-                            .angvel(rand::thread_rng().gen_range(-1., 1.))
+                            .angvel(rng.gen_range(-1., 1.))
                             .linvel(a.x, a.y),
                     )
                     .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(15.).sensor(true));
@@ -634,57 +954,15 @@ pub struct Asteroid(Timer);
 
 pub fn asteroid(
     commands: &mut Commands,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
+    mut interesting_events: ResMut<Events<InterestingEvent>>,
     mut asteroids: Query<(Entity, &mut Asteroid)>,
 ) {
     for (entity, mut asteroid) in asteroids.iter_mut() {
-        asteroid.0.tick(time.delta_seconds());
+        asteroid.0.tick(clock.delta_seconds());
         if asteroid.0.just_finished() {
             commands.despawn_recursive(entity);
-        }
-    }
-}
-
-pub fn moon_attack(
-    commands: &mut Commands,
-    game: Res<Game>,
-    moons: Query<(Entity, &OwnedBy), With<Moon>>,
-    query_ships: Query<
-        (Entity, &crate::space::Orbiter, &crate::game::OwnedBy),
-        With<crate::space::Ship>,
-    >,
-) {
-    if let Some(targeted) = moons
-        .iter()
-        .find(|(_, o)| **o == OwnedBy::Player(0))
-        .map(|(e, _)| e)
-    {
-        for (moon, _) in moons.iter() {
-            let count = game
-                .ship_counts
-                .get(&moon)
-                .unwrap()
-                .get(&OwnedBy::Neutral)
-                .unwrap_or(&0);
-            if *count > 25 {
-                query_ships
-                    .iter()
-                    .filter(|(_, orbiter, owned_by)| {
-                        orbiter.around == moon && **owned_by == crate::game::OwnedBy::Neutral
-                    })
-                    .take(count * 80 / 100)
-                    .for_each(|(entity, _, _)| {
-                        commands.remove_one::<crate::space::Orbiter>(entity);
-                        commands.insert_one(
-                            entity,
-                            crate::space::MoveTowards {
-                                speed: 2000.,
-                                from: moon,
-                                towards: targeted,
-                            },
-                        );
-                    });
-            }
+            interesting_events.send(InterestingEvent::AsteroidSurvived);
         }
     }
 }
@@ -692,7 +970,7 @@ pub fn moon_attack(
 fn scoring(
     game_screen: Res<crate::GameScreen>,
     mut game: ResMut<Game>,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
     (mut event_reader, events): (
         Local<EventReader<crate::game::GameEvents>>,
         Res<Events<crate::game::GameEvents>>,
@@ -706,13 +984,13 @@ fn scoring(
         let moon_points = 10.;
         let planet_points = 100.;
         game.score += if game.neutral_moons == 0 {
-            all_moon_time * time.delta_seconds()
+            all_moon_time * clock.delta_seconds()
         } else {
-            normal_time * time.delta_seconds()
+            normal_time * clock.delta_seconds()
         };
         for event in event_reader.iter(&events) {
             game.score += match event {
-                GameEvents::ShipDamaged(entity, damage) => {
+                GameEvents::ShipDamaged(entity, damage, _) => {
                     if let Ok(OwnedBy::Neutral) = ship_owner.get(*entity) {
                         if *damage > 100 {
                             0.
@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+
+use super::{MatchStart, Screen, CURRENT_SCREEN};
+use crate::input::Action;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PauseButton {
+    Resume,
+    Speed,
+    Settings,
+    Quit,
+}
+
+impl Into<String> for PauseButton {
+    fn into(self) -> String {
+        match self {
+            PauseButton::Resume => "Resume".to_string(),
+            PauseButton::Speed => "Speed".to_string(),
+            PauseButton::Settings => "Settings".to_string(),
+            PauseButton::Quit => "Quit to menu".to_string(),
+        }
+    }
+}
+
+const PAUSE_BUTTONS: &[PauseButton] = &[
+    PauseButton::Resume,
+    PauseButton::Speed,
+    PauseButton::Settings,
+    PauseButton::Quit,
+];
+
+// The overlay shown while the match clock is paused.
+#[derive(Default)]
+pub struct Pause {
+    overlay: Option<Entity>,
+    selected: Option<usize>,
+}
+
+struct PauseItemSelector(usize);
+struct SpeedLabel;
+
+fn select(
+    button: PauseButton,
+    clock: &mut crate::clock::GameClock,
+    game_screen: &mut crate::GameScreen,
+    screen: &mut Screen,
+    match_start: &mut MatchStart,
+) {
+    match button {
+        PauseButton::Resume => clock.resume(),
+        PauseButton::Speed => {
+            if clock.speed() == *crate::clock::SPEEDS.last().unwrap() {
+                clock.set_speed(crate::clock::SPEEDS[0]);
+            } else {
+                clock.change_speed(true);
+            }
+        }
+        PauseButton::Settings => {
+            // the match stays paused behind the settings
+            *match_start = MatchStart::Suspended;
+            game_screen.current_screen = crate::Screen::Settings;
+        }
+        PauseButton::Quit => {
+            screen.save_on_exit = true;
+            game_screen.current_screen = crate::Screen::Menu;
+        }
+    }
+}
+
+pub fn pause_input(
+    mut game_screen: ResMut<crate::GameScreen>,
+    (mut screen, mut pause, mut match_start): (ResMut<Screen>, ResMut<Pause>, ResMut<MatchStart>),
+    mut clock: ResMut<crate::clock::GameClock>,
+    actions: Res<crate::input::Actions>,
+    mut interaction_query: Query<
+        (
+            &bevy::ui::Interaction,
+            &crate::ui::button::ButtonId<PauseButton>,
+        ),
+        (With<Button>, Mutated<bevy::ui::Interaction>),
+    >,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN || !screen.loaded {
+        return;
+    }
+    if *match_start == MatchStart::Suspended {
+        // back from the settings, the key that closed them doesn't resume the match
+        *match_start = MatchStart::New;
+        return;
+    }
+    if actions.just_released(Action::SpeedUp) {
+        clock.change_speed(true);
+    }
+    if actions.just_released(Action::SpeedDown) {
+        clock.change_speed(false);
+    }
+    if !clock.is_paused() {
+        if actions.just_released(Action::Back) {
+            clock.pause();
+            pause.selected = Some(0);
+        }
+        return;
+    }
+
+    if actions.just_released(Action::Back) {
+        clock.resume();
+    } else if actions.just_released(Action::Down) {
+        pause.selected = Some(
+            pause
+                .selected
+                .map(|i| usize::min(PAUSE_BUTTONS.len() - 1, i + 1))
+                .unwrap_or(0),
+        );
+    } else if actions.just_released(Action::Up) {
+        pause.selected = Some(pause.selected.map(|i| i.saturating_sub(1)).unwrap_or(0));
+    } else if actions.just_released(Action::Confirm) {
+        if let Some(button) = pause.selected.and_then(|i| PAUSE_BUTTONS.get(i)) {
+            select(
+                *button,
+                &mut clock,
+                &mut game_screen,
+                &mut screen,
+                &mut match_start,
+            );
+        }
+    }
+    for (interaction, button_id) in interaction_query.iter_mut() {
+        match *interaction {
+            bevy::ui::Interaction::Clicked => select(
+                button_id.0,
+                &mut clock,
+                &mut game_screen,
+                &mut screen,
+                &mut match_start,
+            ),
+            bevy::ui::Interaction::Hovered => {
+                pause.selected = PAUSE_BUTTONS
+                    .iter()
+                    .position(|button| *button == button_id.0)
+            }
+            bevy::ui::Interaction::None => (),
+        }
+    }
+}
+
+pub fn pause_overlay(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    screen: Res<Screen>,
+    mut pause: ResMut<Pause>,
+    clock: Res<crate::clock::GameClock>,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    asset_server: Res<AssetServer>,
+    mut nine_patches: ResMut<Assets<bevy_ninepatch::NinePatchBuilder<()>>>,
+    mut buttons: ResMut<Assets<crate::ui::button::Button>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let shown = game_screen.current_screen == CURRENT_SCREEN && screen.loaded && clock.is_paused();
+    match (shown, pause.overlay) {
+        (false, Some(overlay)) => {
+            commands.despawn_recursive(overlay);
+            pause.overlay = None;
+        }
+        (true, None) => {
+            let button_handle = asset_handles.get_button_handle(
+                &asset_server,
+                &mut materials,
+                &mut nine_patches,
+                &mut buttons,
+            );
+            let button = buttons.get(&button_handle).unwrap();
+            let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+            let menu_indicator: Handle<ColorMaterial> =
+                asset_handles.get_ui_selection_handle(&asset_server, &mut materials);
+            let color_none = materials.add(Color::NONE.into());
+
+            let title = commands
+                .spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(75.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "Paused".to_string(),
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT,
+                            font_size: 75.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                })
+                .current_entity()
+                .unwrap();
+            let speed = commands
+                .spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(20.),
+                            ..Default::default()
+                        },
+                        margin: Rect {
+                            bottom: Val::Px(20.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "".to_string(),
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT_DIM,
+                            font_size: 20.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                })
+                .with(SpeedLabel)
+                .current_entity()
+                .unwrap();
+            let mut children = vec![title, speed];
+
+            for (i, pause_button) in PAUSE_BUTTONS.iter().enumerate() {
+                let row = commands
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::RowReverse,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: color_none.clone(),
+                        ..Default::default()
+                    })
+                    .current_entity()
+                    .unwrap();
+                let button = button.add(
+                    commands,
+                    225.,
+                    50.,
+                    Rect::all(Val::Auto),
+                    font.clone(),
+                    *pause_button,
+                    25.,
+                );
+                commands
+                    .spawn(ImageBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(17.),
+                                width: Val::Px(17.),
+                            },
+                            margin: Rect {
+                                right: Val::Px(15.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        draw: Draw {
+                            is_transparent: true,
+                            is_visible: false,
+                            ..Default::default()
+                        },
+                        material: menu_indicator.clone(),
+                        ..Default::default()
+                    })
+                    .with(PauseItemSelector(i));
+                let indicator = commands.current_entity().unwrap();
+                commands.push_children(row, &[button, indicator]);
+                children.push(row);
+            }
+
+            let overlay = commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::rgba(0., 0., 0.01, 0.7).into()),
+                    ..Default::default()
+                })
+                .with(bevy::ui::FocusPolicy::Block)
+                // keeps clicks from going through to the moons
+                .with(super::ui::UiGameInteractionBlock)
+                .current_entity()
+                .unwrap();
+            commands.push_children(overlay, children.as_slice());
+            pause.overlay = Some(overlay);
+        }
+        _ => (),
+    }
+}
+
+// Dims the match suspended behind the settings and controls screens.
+pub fn spawn_backdrop(commands: &mut Commands, materials: &mut Assets<ColorMaterial>) -> Entity {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0., 0., 0.01, 0.85).into()),
+            ..Default::default()
+        })
+        .with(bevy::ui::FocusPolicy::Pass)
+        .current_entity()
+        .unwrap()
+}
+
+pub fn display_pause(
+    pause: Res<Pause>,
+    clock: Res<crate::clock::GameClock>,
+    mut selectors: Query<(&PauseItemSelector, &mut Draw)>,
+    mut labels: Query<&mut Text, With<SpeedLabel>>,
+) {
+    for (selector, mut draw) in selectors.iter_mut() {
+        draw.is_visible = pause.selected == Some(selector.0);
+    }
+    for mut text in labels.iter_mut() {
+        text.value = format!("Speed: {}x", clock.speed());
+    }
+}
@@ -0,0 +1,615 @@
+use bevy::prelude::*;
+use bevy_rapier2d::{
+    na::Isometry2, physics::RigidBodyHandleComponent, rapier::dynamics::RigidBodySet,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::*;
+use crate::space::{
+    MoveTowards, Orbiter, Rotation, RotationDirection, Shielded, Ship, SpawnShip, SpawnShipType,
+};
+
+pub const SAVE_FILE: &str = "match.save";
+
+pub fn has_saved_match() -> bool {
+    std::path::Path::new(SAVE_FILE).exists()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct SavedTimer {
+    duration: f32,
+    elapsed: f32,
+}
+
+impl From<&Timer> for SavedTimer {
+    fn from(timer: &Timer) -> Self {
+        SavedTimer {
+            duration: timer.duration(),
+            elapsed: timer.elapsed(),
+        }
+    }
+}
+
+impl SavedTimer {
+    fn to_timer(&self, repeating: bool) -> Timer {
+        let mut timer = Timer::from_seconds(self.duration, repeating);
+        timer.set_elapsed(self.elapsed);
+        timer
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct SavedPosition {
+    x: f32,
+    y: f32,
+    angle: f32,
+    angvel: f32,
+}
+
+impl SavedPosition {
+    fn read(bodies: &RigidBodySet, rigid_body: &RigidBodyHandleComponent) -> Self {
+        let body = bodies.get(rigid_body.handle()).unwrap();
+        SavedPosition {
+            x: body.position.translation.x,
+            y: body.position.translation.y,
+            angle: body.position.rotation.angle(),
+            angvel: body.angvel,
+        }
+    }
+
+    fn isometry(&self) -> Isometry2<f32> {
+        Isometry2::new(bevy_rapier2d::na::Vector2::new(self.x, self.y), self.angle)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct SavedOrbiter {
+    speed: f32,
+    offset: f32,
+    direction: RotationDirection,
+    distance: f32,
+    around: Target,
+    rotation: Rotation,
+}
+
+impl SavedOrbiter {
+    fn to_orbiter(&self, targets: &Targets) -> Orbiter {
+        Orbiter {
+            speed: self.speed,
+            offset: self.offset,
+            direction: self.direction,
+            distance: self.distance,
+            around: targets.entity(self.around),
+            rotation: self.rotation,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedSpawnShip {
+    every: SavedTimer,
+    scale: f32,
+    rotation_direction: RotationDirection,
+    hit_points: i32,
+    kind: SpawnShipType,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPlanet {
+    name: String,
+    kind: usize,
+    owner: OwnedBy,
+    position: SavedPosition,
+    fleet_timer: SavedTimer,
+    fleet_last_happened: f32,
+    fleet_iteration: f32,
+    asteroid_timer: SavedTimer,
+    shield: Option<SavedTimer>,
+    #[serde(default)]
+    launcher: Option<SavedTimer>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMoon {
+    index: i32,
+    owner: OwnedBy,
+    position: SavedPosition,
+    orbiter: SavedOrbiter,
+    spawn_ship: SavedSpawnShip,
+    #[serde(default)]
+    launcher: Option<SavedTimer>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SavedCourse {
+    Orbiting(SavedOrbiter),
+    Moving {
+        speed: f32,
+        from: Target,
+        towards: Target,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedShip {
+    owner: OwnedBy,
+    hit_points: i32,
+    speed: f32,
+    tier: usize,
+    x: f32,
+    y: f32,
+    scale: f32,
+    course: SavedCourse,
+    self_destruct: Option<SavedTimer>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SavedMissileTarget {
+    Body(Target),
+    // index in the saved ships
+    Ship(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMissile {
+    owner: OwnedBy,
+    x: f32,
+    y: f32,
+    target: SavedMissileTarget,
+    lifetime: SavedTimer,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedAsteroid {
+    position: SavedPosition,
+    linvel: (f32, f32),
+    timer: SavedTimer,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedMatch {
+    seed: u64,
+    #[serde(default)]
+    rng_position: u64,
+    tick: u64,
+    clock_elapsed: f32,
+    state: GameState,
+    score: f32,
+    ratio: Ratio,
+    elapsed: f32,
+    planet: SavedPlanet,
+    moons: Vec<SavedMoon>,
+    ships: Vec<SavedShip>,
+    asteroids: Vec<SavedAsteroid>,
+    #[serde(default)]
+    level: Option<usize>,
+    #[serde(default)]
+    adaptation: adaptive::Adaptation,
+    #[serde(default)]
+    missiles: Vec<SavedMissile>,
+}
+
+struct Targets {
+    planet: Entity,
+    moons: Vec<(i32, Entity)>,
+}
+
+impl Targets {
+    fn target(&self, entity: Entity) -> Target {
+        self.moons
+            .iter()
+            .find(|(_, moon)| *moon == entity)
+            .map(|(index, _)| Target::Moon(*index))
+            .unwrap_or(Target::Planet)
+    }
+
+    fn entity(&self, target: Target) -> Entity {
+        match target {
+            Target::Planet => self.planet,
+            Target::Moon(index) => self
+                .moons
+                .iter()
+                .find(|(i, _)| *i == index)
+                .map(|(_, entity)| *entity)
+                .unwrap_or(self.planet),
+        }
+    }
+}
+
+impl SavedMatch {
+    pub fn load(path: &str) -> Result<SavedMatch, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn level(&self) -> Option<usize> {
+        self.level
+    }
+
+    pub fn restore(
+        self,
+        commands: &mut Commands,
+        game: &mut Game,
+        clock: &mut crate::clock::GameClock,
+        rng: &mut crate::rng::MatchRng,
+        adaptation: &mut adaptive::Adaptation,
+    ) {
+        clock.restore(self.tick, self.clock_elapsed);
+        rng.resume(self.seed, self.rng_position);
+        game.state = self.state;
+        game.score = self.score;
+        game.ratio = self.ratio;
+        game.elapsed = self.elapsed;
+        game.level = self.level;
+        *adaptation = self.adaptation;
+
+        let planet = spawn_planet(
+            commands,
+            Planet {
+                name: self.planet.name,
+                kind: self.planet.kind,
+            },
+            self.planet.position.isometry(),
+            self.planet.position.angvel,
+            self.planet.owner,
+            (
+                PlanetFleet {
+                    timer: self.planet.fleet_timer.to_timer(true),
+                    last_happened: self.planet.fleet_last_happened,
+                    iteration: self.planet.fleet_iteration,
+                },
+                AsteroidBelt {
+                    timer: self.planet.asteroid_timer.to_timer(true),
+                },
+            ),
+        );
+        if let Some(launcher) = self.planet.launcher {
+            commands.insert_one(
+                planet,
+                missile::Launcher {
+                    timer: launcher.to_timer(true),
+                },
+            );
+        }
+        if let Some(shield) = self.planet.shield {
+            let shield = commands
+                .spawn((Shielded {
+                    timer: shield.to_timer(false),
+                },))
+                .current_entity()
+                .unwrap();
+            commands.push_children(planet, &[shield]);
+        }
+
+        let mut targets = Targets {
+            planet,
+            moons: vec![],
+        };
+        for moon in self.moons.into_iter() {
+            let orbiter = moon.orbiter.to_orbiter(&targets);
+            let entity = spawn_moon(
+                commands,
+                Moon {
+                    index: moon.index,
+                    planet,
+                },
+                moon.owner,
+                (
+                    orbiter,
+                    SpawnShip {
+                        every: moon.spawn_ship.every.to_timer(true),
+                        scale: moon.spawn_ship.scale,
+                        rotation_direction: moon.spawn_ship.rotation_direction,
+                        hit_points: moon.spawn_ship.hit_points,
+                        kind: moon.spawn_ship.kind,
+                    },
+                ),
+                moon.position.isometry(),
+                moon.position.angvel,
+            );
+            if let Some(launcher) = moon.launcher {
+                commands.insert_one(
+                    entity,
+                    missile::Launcher {
+                        timer: launcher.to_timer(true),
+                    },
+                );
+            }
+            targets.moons.push((moon.index, entity));
+        }
+
+        let mut ships = vec![];
+        for ship in self.ships.into_iter() {
+            commands.spawn((
+                Transform {
+                    translation: Vec3::new(ship.x, ship.y, crate::Z_SHIP),
+                    scale: Vec3::splat(ship.scale),
+                    ..Default::default()
+                },
+                GlobalTransform::default(),
+            ));
+            let entity = commands.current_entity().unwrap();
+            ships.push(entity);
+            commands
+                .with(
+                    bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
+                        .translation(ship.x, ship.y)
+                        .user_data(entity.to_bits() as u128),
+                )
+                .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(
+                    ship.scale / 0.15 * 5.,
+                ))
+                .with(ship.owner)
+                .with(Ship {
+                    hit_points: ship.hit_points,
+                    speed: ship.speed,
+                    tier: ship.tier,
+                });
+            match ship.course {
+                SavedCourse::Orbiting(orbiter) => {
+                    commands.with(orbiter.to_orbiter(&targets));
+                }
+                SavedCourse::Moving {
+                    speed,
+                    from,
+                    towards,
+                } => {
+                    commands.with(MoveTowards {
+                        speed,
+                        from: targets.entity(from),
+                        towards: targets.entity(towards),
+                    });
+                }
+            }
+            if let Some(self_destruct) = ship.self_destruct {
+                commands.with(SelfDestruct(self_destruct.to_timer(false)));
+            }
+        }
+
+        for saved in self.missiles.into_iter() {
+            let target = match saved.target {
+                SavedMissileTarget::Body(target) => targets.entity(target),
+                SavedMissileTarget::Ship(index) => match ships.get(index) {
+                    Some(ship) => *ship,
+                    None => continue,
+                },
+            };
+            missile::spawn_missile(
+                commands,
+                Vec3::new(saved.x, saved.y, crate::Z_SHIP),
+                saved.owner,
+                target,
+                saved.lifetime.to_timer(false),
+            );
+        }
+
+        for asteroid in self.asteroids.into_iter() {
+            commands.spawn((
+                Transform {
+                    translation: Vec3::new(asteroid.position.x, asteroid.position.y, crate::Z_SHIP),
+                    scale: Vec3::splat(0.4),
+                    ..Default::default()
+                },
+                GlobalTransform::default(),
+            ));
+            let entity = commands.current_entity().unwrap();
+            commands
+                .with(
+                    bevy_rapier2d::rapier::dynamics::RigidBodyBuilder::new_dynamic()
+                        .position(asteroid.position.isometry())
+                        .user_data(entity.to_bits() as u128)
+                        .angvel(asteroid.position.angvel)
+                        .linvel(asteroid.linvel.0, asteroid.linvel.1),
+                )
+                .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(15.).sensor(true))
+                .with(Asteroid(asteroid.timer.to_timer(false)))
+                .with(ScreenTag);
+        }
+    }
+}
+
+pub fn save_match(
+    mut screen: ResMut<Screen>,
+    (game, clock, rng, adaptation): (
+        Res<Game>,
+        Res<crate::clock::GameClock>,
+        Res<crate::rng::MatchRng>,
+        Res<adaptive::Adaptation>,
+    ),
+    (replay, bodies): (Res<crate::replay::ReplayState>, Res<RigidBodySet>),
+    planets: Query<(
+        Entity,
+        &Planet,
+        &OwnedBy,
+        &PlanetFleet,
+        &AsteroidBelt,
+        &RigidBodyHandleComponent,
+    )>,
+    moons: Query<(
+        Entity,
+        &Moon,
+        &OwnedBy,
+        &Orbiter,
+        &SpawnShip,
+        &RigidBodyHandleComponent,
+    )>,
+    ships: Query<(
+        Entity,
+        &Ship,
+        &OwnedBy,
+        &Transform,
+        Option<&Orbiter>,
+        Option<&MoveTowards>,
+        Option<&SelfDestruct>,
+    )>,
+    (asteroids, shields): (
+        Query<(&Asteroid, &RigidBodyHandleComponent)>,
+        Query<(&Shielded, &Parent)>,
+    ),
+    (launchers, missiles): (
+        Query<&missile::Launcher>,
+        Query<(&missile::Missile, &GlobalTransform)>,
+    ),
+) {
+    if !screen.save_on_exit || !screen.loaded {
+        return;
+    }
+    screen.save_on_exit = false;
+    if replay.is_playback() || game.state != GameState::Play {
+        return;
+    }
+    let (planet_entity, planet, owner, fleet, asteroid_belt, rigid_body) =
+        if let Some(planet) = planets.iter().next() {
+            planet
+        } else {
+            return;
+        };
+    let targets = Targets {
+        planet: planet_entity,
+        moons: moons
+            .iter()
+            .map(|(entity, moon, ..)| (moon.index, entity))
+            .collect(),
+    };
+    let save_orbiter = |orbiter: &Orbiter| SavedOrbiter {
+        speed: orbiter.speed,
+        offset: orbiter.offset,
+        direction: orbiter.direction,
+        distance: orbiter.distance,
+        around: targets.target(orbiter.around),
+        rotation: orbiter.rotation,
+    };
+    let save_launcher = |entity: Entity| {
+        launchers
+            .get(entity)
+            .ok()
+            .map(|launcher| SavedTimer::from(&launcher.timer))
+    };
+    let (ship_entities, saved_ships): (Vec<_>, Vec<_>) = ships
+        .iter()
+        .filter_map(
+            |(entity, ship, owner, transform, orbiter, move_towards, self_destruct)| {
+                let course = match (orbiter, move_towards) {
+                    (Some(orbiter), _) => SavedCourse::Orbiting(save_orbiter(orbiter)),
+                    (None, Some(move_towards)) => SavedCourse::Moving {
+                        speed: move_towards.speed,
+                        from: targets.target(move_towards.from),
+                        towards: targets.target(move_towards.towards),
+                    },
+                    (None, None) => return None,
+                };
+                Some((
+                    entity,
+                    SavedShip {
+                        owner: owner.clone(),
+                        hit_points: ship.hit_points,
+                        speed: ship.speed,
+                        tier: ship.tier,
+                        x: transform.translation.x,
+                        y: transform.translation.y,
+                        scale: transform.scale.x,
+                        course,
+                        self_destruct: self_destruct.map(|timer| SavedTimer::from(&timer.0)),
+                    },
+                ))
+            },
+        )
+        .unzip();
+
+    let saved = SavedMatch {
+        seed: rng.seed(),
+        rng_position: rng.position(),
+        tick: clock.tick(),
+        clock_elapsed: clock.seconds_since_start(),
+        state: game.state,
+        score: game.score,
+        ratio: game.ratio,
+        elapsed: game.elapsed,
+        level: game.level,
+        adaptation: adaptation.clone(),
+        planet: SavedPlanet {
+            name: planet.name.clone(),
+            kind: planet.kind,
+            owner: owner.clone(),
+            position: SavedPosition::read(&bodies, rigid_body),
+            fleet_timer: SavedTimer::from(&fleet.timer),
+            fleet_last_happened: fleet.last_happened,
+            fleet_iteration: fleet.iteration,
+            asteroid_timer: SavedTimer::from(&asteroid_belt.timer),
+            shield: shields
+                .iter()
+                .find(|(_, parent)| parent.0 == planet_entity)
+                .map(|(shielded, _)| SavedTimer::from(&shielded.timer)),
+            launcher: save_launcher(planet_entity),
+        },
+        moons: moons
+            .iter()
+            .map(
+                |(entity, moon, owner, orbiter, spawn_ship, rigid_body)| SavedMoon {
+                    index: moon.index,
+                    owner: owner.clone(),
+                    position: SavedPosition::read(&bodies, rigid_body),
+                    orbiter: save_orbiter(orbiter),
+                    spawn_ship: SavedSpawnShip {
+                        every: SavedTimer::from(&spawn_ship.every),
+                        scale: spawn_ship.scale,
+                        rotation_direction: spawn_ship.rotation_direction,
+                        hit_points: spawn_ship.hit_points,
+                        kind: spawn_ship.kind,
+                    },
+                    launcher: save_launcher(entity),
+                },
+            )
+            .collect(),
+        missiles: missiles
+            .iter()
+            .filter_map(|(missile, gt)| {
+                let is_body = missile.target == planet_entity
+                    || targets
+                        .moons
+                        .iter()
+                        .any(|(_, moon)| *moon == missile.target);
+                let target = match ship_entities
+                    .iter()
+                    .position(|ship| *ship == missile.target)
+                {
+                    Some(index) => SavedMissileTarget::Ship(index),
+                    None if is_body => SavedMissileTarget::Body(targets.target(missile.target)),
+                    // its target is gone, the missile would be too
+                    None => return None,
+                };
+                Some(SavedMissile {
+                    owner: missile.owner.clone(),
+                    x: gt.translation.x,
+                    y: gt.translation.y,
+                    target,
+                    lifetime: SavedTimer::from(&missile.lifetime),
+                })
+            })
+            .collect(),
+        ships: saved_ships,
+        asteroids: asteroids
+            .iter()
+            .map(|(asteroid, rigid_body)| {
+                let linvel = bodies.get(rigid_body.handle()).unwrap().linvel;
+                SavedAsteroid {
+                    position: SavedPosition::read(&bodies, rigid_body),
+                    linvel: (linvel.x, linvel.y),
+                    timer: SavedTimer::from(&asteroid.0),
+                }
+            })
+            .collect(),
+    };
+
+    match saved.save(SAVE_FILE) {
+        Ok(()) => info!("match saved to {}", SAVE_FILE),
+        Err(err) => warn!("could not save match: {}", err),
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+use super::{Game, InterestingEvent, OwnedBy};
+
+#[derive(Default, Debug, Clone)]
+pub struct OwnerStats {
+    pub built: usize,
+    pub lost: usize,
+    // ships of the other sides destroyed by this one
+    pub destroyed: usize,
+    pub moons_captured: usize,
+    pub moons_lost: usize,
+    pub largest_fleet: usize,
+    // most ships orbiting the moons at the same time
+    pub peak_ships: usize,
+}
+
+// Stats of the current match, or of the last one once it is over. Times are in seconds since
+// its start.
+#[derive(Default)]
+pub struct GameStats {
+    pub owners: std::collections::HashMap<OwnedBy, OwnerStats>,
+    pub asteroids_survived: usize,
+    pub first_capture: Option<f32>,
+    pub shield_drop: Option<f32>,
+    // `Game::ship_counts` taken every `SAMPLE_EVERY` seconds
+    pub timeline: Vec<Sample>,
+    // when a moon was captured and by whom
+    pub captures: Vec<(f32, OwnedBy)>,
+    pub fleet_waves: Vec<f32>,
+}
+
+// seconds of match between two samples of the timeline
+pub const SAMPLE_EVERY: f32 = 1.;
+
+pub struct Sample {
+    pub time: f32,
+    pub ship_counts: std::collections::HashMap<Entity, std::collections::HashMap<OwnedBy, usize>>,
+}
+
+impl Sample {
+    // ships of `owner`, or of everyone else when `others` is set
+    pub fn ships(&self, owner: &OwnedBy, others: bool) -> usize {
+        self.ship_counts
+            .values()
+            .flatten()
+            .filter(|(counted, _)| (*counted == owner) != others)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+impl GameStats {
+    fn owner(&mut self, owner: &OwnedBy) -> &mut OwnerStats {
+        self.owners.entry(owner.clone()).or_default()
+    }
+
+    // sides that took part in the match, the player first and the planet last
+    pub fn sides(&self) -> Vec<(&OwnedBy, &OwnerStats)> {
+        let mut sides = self.owners.iter().collect::<Vec<_>>();
+        sides.sort_by_key(|(owner, _)| match owner {
+            OwnedBy::Player(n) => *n,
+            OwnedBy::Neutral => usize::MAX,
+        });
+        sides
+    }
+}
+
+pub fn update_stats(
+    mut stats: ResMut<GameStats>,
+    game: Res<Game>,
+    game_screen: Res<crate::GameScreen>,
+    (mut interesting_event_reader, interesting_events): (
+        Local<EventReader<InterestingEvent>>,
+        ResMut<Events<InterestingEvent>>,
+    ),
+) {
+    for event in interesting_event_reader.iter(&interesting_events) {
+        match event {
+            InterestingEvent::ShipBuilt(owner) => stats.owner(owner).built += 1,
+            InterestingEvent::ShipDestroyed { owner, by } => {
+                stats.owner(owner).lost += 1;
+                if let Some(by) = by {
+                    stats.owner(by).destroyed += 1;
+                }
+            }
+            InterestingEvent::MoonCaptured { from, to } => {
+                stats.owner(from).moons_lost += 1;
+                stats.owner(to).moons_captured += 1;
+                if *to == OwnedBy::Player(0) && stats.first_capture.is_none() {
+                    stats.first_capture = Some(game.elapsed);
+                }
+                stats.captures.push((game.elapsed, to.clone()));
+            }
+            InterestingEvent::FleetSent { owner, size } => {
+                let fleet = &mut stats.owner(owner).largest_fleet;
+                *fleet = (*fleet).max(*size);
+            }
+            InterestingEvent::FleetWave(size) => {
+                let fleet = &mut stats.owner(&OwnedBy::Neutral).largest_fleet;
+                *fleet = (*fleet).max(*size);
+                stats.fleet_waves.push(game.elapsed);
+            }
+            InterestingEvent::AsteroidSurvived => stats.asteroids_survived += 1,
+            InterestingEvent::ShieldDropped => {
+                if stats.shield_drop.is_none() {
+                    stats.shield_drop = Some(game.elapsed);
+                }
+            }
+        }
+    }
+
+    if game_screen.current_screen == crate::Screen::Game {
+        let mut counts = std::collections::HashMap::new();
+        for (owner, count) in game.ship_counts.values().flatten() {
+            *counts.entry(owner).or_insert(0) += count;
+        }
+        for (owner, count) in counts {
+            let peak = &mut stats.owner(owner).peak_ships;
+            *peak = (*peak).max(count);
+        }
+
+        let due = stats
+            .timeline
+            .last()
+            .map(|sample| game.elapsed - sample.time >= SAMPLE_EVERY)
+            .unwrap_or(true);
+        if due {
+            stats.timeline.push(Sample {
+                time: game.elapsed,
+                ship_counts: game.ship_counts.clone(),
+            });
+        }
+    }
+}
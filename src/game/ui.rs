@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use tracing::info;
 
 use super::*;
+use crate::input::Action;
 
 pub struct UiSelected;
 pub struct UiHighlighted;
@@ -17,10 +18,14 @@ pub fn setup(
     mut nine_patches: ResMut<Assets<bevy_ninepatch::NinePatchBuilder<()>>>,
     mut asset_handles: ResMut<crate::AssetHandles>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
         info!("Loading");
 
+        // usually done by the menu, but a replay starts directly in game
+        asset_handles.get_game_handles(&asset_server, &mut materials, &mut atlases);
+
         let material_none = materials.add(Color::NONE.into());
 
         let font = asset_handles.get_font_sub_handle(&asset_server);
@@ -169,13 +174,21 @@ pub fn setup(
     }
 }
 
-pub fn timer(game: Res<Game>, mut timer: Query<&mut Text, With<UiTime>>) {
+pub fn timer(
+    game: Res<Game>,
+    clock: Res<crate::clock::GameClock>,
+    mut timer: Query<&mut Text, With<UiTime>>,
+) {
     for mut timer in timer.iter_mut() {
         let secs = game.elapsed.floor() as i32;
         let ms = ((game.elapsed - secs as f32) * 1000.) as i32;
         let m = secs / 60;
         let secs = secs % 60;
-        timer.value = format!("{:02}:{:02}.{}", m, secs, ms);
+        timer.value = if clock.speed() == 1. {
+            format!("{:02}:{:02}.{}", m, secs, ms)
+        } else {
+            format!("{:02}:{:02}.{} ({}x)", m, secs, ms, clock.speed())
+        };
     }
 }
 
@@ -204,6 +217,38 @@ pub struct InteractionBox {
 
 pub struct Interacted;
 
+pub fn interaction_box(
+    commands: &mut Commands,
+    query_planet: Query<(Entity, &Planet), Added<Planet>>,
+    query_moon: Query<Entity, Added<Moon>>,
+) {
+    for (entity, planet) in query_planet.iter() {
+        commands.insert(
+            entity,
+            (
+                Interaction::None,
+                InteractionBox {
+                    radius: planet.radius() + 5.,
+                },
+            ),
+        );
+    }
+    for entity in query_moon.iter() {
+        commands.insert(entity, (Interaction::None, InteractionBox { radius: 30. }));
+    }
+}
+
+pub fn select_start_moon(
+    mut events: ResMut<Events<InteractionEvent>>,
+    query_moon: Query<(Entity, &OwnedBy), Added<Moon>>,
+) {
+    for (entity, owner) in query_moon.iter() {
+        if *owner == OwnedBy::Player(0) {
+            events.send(InteractionEvent::Clicked(Some(entity)));
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct State {
     cursor_moved_event_reader: EventReader<CursorMoved>,
@@ -214,11 +259,12 @@ pub struct State {
 
 pub fn focus_system(
     mut state: Local<State>,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: Res<crate::input::Actions>,
     cursor_moved_events: Res<Events<CursorMoved>>,
     touches_input: Res<Touches>,
     wnds: Res<Windows>,
     mut events: ResMut<Events<InteractionEvent>>,
+    camera_query: Query<(&bevy::render::camera::Camera, &GlobalTransform)>,
     block_query: Query<(&GlobalTransform, &Node), With<UiGameInteractionBlock>>,
     mut node_query: Query<(
         Entity,
@@ -228,16 +274,10 @@ pub fn focus_system(
     )>,
 ) {
     if let Some(cursor_moved) = state.cursor_moved_event_reader.latest(&cursor_moved_events) {
-        state.cursor_position = Vec2::new(
-            cursor_moved.position.x - wnds.get_primary().unwrap().width() as f32 / 2.,
-            cursor_moved.position.y - wnds.get_primary().unwrap().height() as f32 / 2.,
-        );
+        state.cursor_position = cursor_moved.position;
     }
     if let Some(touch) = touches_input.get_pressed(0) {
-        state.cursor_position = Vec2::new(
-            touch.position.x - wnds.get_primary().unwrap().width() as f32 / 2.,
-            touch.position.y - wnds.get_primary().unwrap().height() as f32 / 2.,
-        );
+        state.cursor_position = touch.position;
     }
     for (global_transform, node) in block_query.iter() {
         let position = global_transform.translation;
@@ -245,17 +285,25 @@ pub fn focus_system(
         let extents = node.size / 2.0;
         let min = ui_position - extents;
         let max = ui_position + extents;
-        if (min.x..max.x)
-            .contains(&(state.cursor_position.x + wnds.get_primary().unwrap().width() as f32 / 2.))
-            && (min.y..max.y).contains(
-                &(state.cursor_position.y + wnds.get_primary().unwrap().height() as f32 / 2.),
-            )
+        if (min.x..max.x).contains(&state.cursor_position.x)
+            && (min.y..max.y).contains(&state.cursor_position.y)
         {
             return;
         }
     }
+    let world_position = match camera_query
+        .iter()
+        .find(|(camera, _)| super::camera::is_world_camera(camera))
+    {
+        Some((_, camera_transform)) => super::camera::screen_to_world(
+            state.cursor_position,
+            wnds.get_primary().unwrap(),
+            camera_transform,
+        ),
+        None => return,
+    };
 
-    if mouse_button_input.just_released(MouseButton::Left) || touches_input.just_released(0) {
+    if actions.just_released(Action::Select) || touches_input.just_released(0) {
         for (_entity, _node, _global_transform, interaction) in node_query.iter_mut() {
             if let Some(mut interaction) = interaction {
                 if *interaction == Interaction::Clicked {
@@ -265,8 +313,7 @@ pub fn focus_system(
         }
     }
 
-    let mouse_clicked =
-        mouse_button_input.just_pressed(MouseButton::Left) || touches_input.just_released(0);
+    let mouse_clicked = actions.just_pressed(Action::Select) || touches_input.just_released(0);
     let mut hovered_entity = None;
     let mut clicked_entity = None;
 
@@ -278,7 +325,7 @@ pub fn focus_system(
                 if global_transform
                     .translation
                     .truncate()
-                    .distance(state.cursor_position)
+                    .distance(world_position)
                     < node.radius
                 {
                     Some((entity, interaction))
@@ -496,6 +543,7 @@ pub fn interaction(
 pub enum UiElement {
     Owner,
     ShipCount,
+    ShipClass,
     Status,
     SelectedRatio,
     SelectedCount,
@@ -503,6 +551,9 @@ pub enum UiElement {
 
 pub struct Panel(Entity);
 
+// switches the ships built by the selected moon when clicked
+pub struct ShipClassButton;
+
 pub fn ui_update_on_interaction_event(
     commands: &mut Commands,
     game: Res<Game>,
@@ -650,6 +701,39 @@ pub fn ui_update_on_interaction_event(
                 .with(UiElement::ShipCount)
                 .current_entity()
                 .unwrap();
+            let ui_ship_class = commands
+                .spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(15.),
+                            ..Default::default()
+                        },
+                        align_self: AlignSelf::Center,
+                        ..Default::default()
+                    },
+                    text: Text {
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT_DARK,
+                            font_size: 15.,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(Panel(ui_target_entity))
+                .with(UiElement::ShipClass)
+                .current_entity()
+                .unwrap();
+            if main {
+                commands.with_bundle((
+                    Button,
+                    bevy::ui::Interaction::default(),
+                    bevy::ui::FocusPolicy::Block,
+                    ShipClassButton,
+                ));
+            }
             let ui_under_attack = commands
                 .spawn(TextBundle {
                     style: Style {
@@ -678,7 +762,13 @@ pub fn ui_update_on_interaction_event(
 
             commands.push_children(
                 ui_target_entity,
-                &[ui_name, ui_owner, ui_ships_orbiting_count, ui_under_attack],
+                &[
+                    ui_name,
+                    ui_owner,
+                    ui_ships_orbiting_count,
+                    ui_ship_class,
+                    ui_under_attack,
+                ],
             );
             if main {
                 let ui_ship_selection = commands
@@ -776,41 +866,6 @@ pub fn ui_update_on_interaction_event(
     }
 }
 
-pub fn ship_count(
-    mut game: ResMut<Game>,
-    mut events: ResMut<Events<GameEvents>>,
-    query_moon: Query<(Entity, &OwnedBy), With<Moon>>,
-    query_planet: Query<Entity, With<Planet>>,
-    query_ships: Query<(&crate::space::Orbiter, &crate::game::OwnedBy), With<crate::space::Ship>>,
-) {
-    let mut neutral_moons = 0;
-    for (moon_entity, owner) in query_moon.iter() {
-        let ships_orbiting_count = query_ships
-            .iter()
-            .filter(|(orbiter, _)| orbiter.around == moon_entity)
-            .fold(
-                std::collections::HashMap::new(),
-                |mut counts, (_, owned_by)| {
-                    let count = counts.entry(owned_by.clone()).or_insert(0);
-                    *count += 1;
-                    counts
-                },
-            );
-        game.ship_counts.insert(moon_entity, ships_orbiting_count);
-        if let OwnedBy::Neutral = owner {
-            neutral_moons += 1;
-        }
-    }
-    if let Some(planet) = query_planet.iter().next() {
-        game.ship_counts
-            .insert(planet, std::collections::HashMap::new());
-        if neutral_moons == 0 && game.neutral_moons != 0 {
-            events.send(GameEvents::PlanetShield(planet, 10.0))
-        }
-    }
-    game.neutral_moons = neutral_moons;
-}
-
 pub fn ui_update(
     commands: &mut Commands,
     game: Res<Game>,
@@ -819,7 +874,10 @@ pub fn ui_update(
     mut materials: ResMut<Assets<ColorMaterial>>,
     query_ui_selected: Query<Entity, With<UiSelected>>,
     query_ui_highlighted: Query<Entity, With<UiHighlighted>>,
-    query_owner: Query<&crate::game::OwnedBy>,
+    (query_owner, query_spawner): (
+        Query<&crate::game::OwnedBy>,
+        Query<&crate::space::SpawnShip>,
+    ),
     mut ui_texts: Query<(&mut Text, &UiElement, &Panel)>,
     mut ui_nodes: Query<(Entity, Option<&mut Children>, &UiElement, &Panel), Without<Text>>,
 ) {
@@ -833,6 +891,7 @@ pub fn ui_update(
             if let Some(ui_main) = ui_entity {
                 let owner = query_owner.get(entity).unwrap();
                 let ships_orbiting_count = game.ship_counts.get(&entity).unwrap();
+                let main = query_ui_selected.iter().next() == Some(ui_main);
                 for (mut ui_text, element, panel) in ui_texts.iter_mut() {
                     if panel.0 != ui_main {
                         continue;
@@ -854,6 +913,15 @@ pub fn ui_update(
                                 n => format!("{} ships", n),
                             };
                         }
+                        UiElement::ShipClass => {
+                            ui_text.value = match (query_spawner.get(entity), owner) {
+                                (Ok(spawner), crate::game::OwnedBy::Player(0)) if main => {
+                                    format!("building {} ships (C to change)", spawner.kind)
+                                }
+                                (Ok(spawner), _) => format!("building {} ships", spawner.kind),
+                                (Err(_), _) => "".to_string(),
+                            };
+                        }
                         UiElement::Status => {
                             if ships_orbiting_count.len() > 1 {
                                 ui_text.value = "Under Attack".to_string();
@@ -960,50 +1028,161 @@ pub fn ui_update(
     }
 }
 
+// Moon actions, the number keys by default, select the moon with the same index, or target it
+// with Shift held. Goes through the same events as the mouse.
+pub fn keyboard_control(
+    (game_screen, screen): (Res<crate::GameScreen>, Res<Screen>),
+    clock: Res<crate::clock::GameClock>,
+    actions: Res<crate::input::Actions>,
+    mut events: ResMut<Events<InteractionEvent>>,
+    query_moon: Query<(Entity, &Moon)>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN || !screen.loaded || clock.is_paused() {
+        return;
+    }
+    let target = actions.pressed(Action::TargetModifier);
+
+    for (i, action) in Action::MOONS.iter().enumerate() {
+        if !actions.just_pressed(*action) {
+            continue;
+        }
+        if let Some((moon, _)) = query_moon
+            .iter()
+            .find(|(_, moon)| moon.index == i as i32 + 1)
+        {
+            if target {
+                events.send(InteractionEvent::Hovered(Some(moon)));
+            } else {
+                events.send(InteractionEvent::Clicked(Some(moon)));
+            }
+        }
+    }
+}
+
+// Directions move the target between the planet and its moons, then the target can be
+// selected. Made for gamepads, but works with any binding.
+pub fn target_control(
+    mut game: ResMut<Game>,
+    (game_screen, screen): (Res<crate::GameScreen>, Res<Screen>),
+    (actions, clock): (Res<crate::input::Actions>, Res<crate::clock::GameClock>),
+    mut events: ResMut<Events<InteractionEvent>>,
+    query_moon: Query<(Entity, &Moon)>,
+    query_planet: Query<Entity, With<Planet>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN || !screen.loaded || clock.is_paused() {
+        return;
+    }
+
+    let step = if actions.just_pressed(Action::Right) || actions.just_pressed(Action::Up) {
+        Some(1)
+    } else if actions.just_pressed(Action::Left) || actions.just_pressed(Action::Down) {
+        Some(-1)
+    } else {
+        None
+    };
+    if let Some(step) = step {
+        let mut targets = query_planet
+            .iter()
+            .map(|planet| (planet, 0))
+            .chain(query_moon.iter().map(|(entity, moon)| (entity, moon.index)))
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|(_, index)| *index);
+        let count = targets.len() as i32;
+        let mut current = game
+            .targeted
+            .or(game.selected)
+            .and_then(|entity| targets.iter().position(|(target, _)| *target == entity))
+            .map(|i| i as i32);
+        // the selected moon can't be targeted, skip it
+        for _ in 0..count {
+            let next = current.map(|i| (i + step + count) % count).unwrap_or(0);
+            current = Some(next);
+            let target = targets[next as usize].0;
+            if Some(target) != game.selected {
+                events.send(InteractionEvent::Hovered(Some(target)));
+                break;
+            }
+        }
+    }
+
+    if actions.just_pressed(Action::TargetPlanet) {
+        if let Some(planet) = query_planet.iter().next() {
+            events.send(InteractionEvent::Hovered(Some(planet)));
+        }
+    }
+    if actions.just_pressed(Action::SelectTarget) {
+        if let Some(targeted) = game.targeted {
+            events.send(InteractionEvent::Clicked(Some(targeted)));
+        }
+    }
+    if actions.just_pressed(Action::Deselect) {
+        events.send(InteractionEvent::Clicked(None));
+    }
+    if actions.just_pressed(Action::CycleRatio) {
+        game.ratio.next();
+    }
+    if actions.just_pressed(Action::CycleRatioBack) {
+        game.ratio.previous();
+    }
+}
+
 pub fn orders(
-    commands: &mut Commands,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: Res<crate::input::Actions>,
     game: Res<Game>,
+    (replay, clock): (
+        Res<crate::replay::ReplayState>,
+        Res<crate::clock::GameClock>,
+    ),
+    mut orders: ResMut<Events<Order>>,
     query_owner: Query<&crate::game::OwnedBy>,
-    query_ships: Query<
-        (Entity, &crate::space::Orbiter, &crate::game::OwnedBy),
-        With<crate::space::Ship>,
-    >,
 ) {
-    if mouse_button_input.just_pressed(MouseButton::Right)
+    if actions.just_pressed(Action::Send)
+        && !replay.is_playback()
+        && !clock.is_paused()
         && game.selected.is_some()
         && game.targeted.is_some()
+        && query_owner.get(game.selected.unwrap()).ok() == Some(&crate::game::OwnedBy::Player(0))
     {
-        let selected = game.selected.unwrap();
-        let targeted = game.targeted.unwrap();
-        let owner = query_owner.get(selected).unwrap();
-        if *owner != crate::game::OwnedBy::Player(0) {
-            return;
-        }
-        let ship_count = *game
-            .ship_counts
-            .get(&selected)
-            .unwrap()
-            .get(&crate::game::OwnedBy::Player(0))
-            .unwrap_or(&0);
-
-        query_ships
-            .iter()
-            .filter(|(_, orbiter, owned_by)| {
-                orbiter.around == selected && **owned_by == crate::game::OwnedBy::Player(0)
-            })
-            .take(game.ratio.of(ship_count))
-            .for_each(|(entity, _, _)| {
-                commands.remove_one::<crate::space::Orbiter>(entity);
-                commands.insert_one(
-                    entity,
-                    crate::space::MoveTowards {
-                        speed: 2500.,
-                        from: selected,
-                        towards: targeted,
-                    },
-                );
+        orders.send(Order::SendShips {
+            owner: crate::game::OwnedBy::Player(0),
+            from: game.selected.unwrap(),
+            towards: game.targeted.unwrap(),
+            ratio: game.ratio,
+        });
+    }
+}
+
+pub fn change_ship_class(
+    game: Res<Game>,
+    actions: Res<crate::input::Actions>,
+    (replay, clock): (
+        Res<crate::replay::ReplayState>,
+        Res<crate::clock::GameClock>,
+    ),
+    mut orders: ResMut<Events<Order>>,
+    query_spawner: Query<(&crate::space::SpawnShip, &crate::game::OwnedBy)>,
+    interaction_query: Query<
+        &bevy::ui::Interaction,
+        (With<ShipClassButton>, Mutated<bevy::ui::Interaction>),
+    >,
+) {
+    let clicked = interaction_query
+        .iter()
+        .any(|interaction| *interaction == bevy::ui::Interaction::Clicked);
+    if !(clicked || actions.just_pressed(Action::SwitchShipClass))
+        || replay.is_playback()
+        || clock.is_paused()
+    {
+        return;
+    }
+    if let Some(moon) = game.selected {
+        if let Ok((spawner, crate::game::OwnedBy::Player(0))) = query_spawner.get(moon) {
+            orders.send(Order::SwitchShipClass {
+                owner: crate::game::OwnedBy::Player(0),
+                moon,
+                class: spawner.kind.next(),
             });
+        }
     }
 }
 
@@ -0,0 +1,88 @@
+use bevy::{
+    input::gamepad::{Gamepad, GamepadAxis, GamepadAxisType, GamepadEvent, GamepadEventType},
+    prelude::*,
+};
+use tracing::info;
+
+// how far a stick must be pushed to count as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// Every connected gamepad controls the game, so a controller can be passed around on a couch.
+#[derive(Default)]
+pub struct Gamepads {
+    connected: Vec<Gamepad>,
+    // direction the left stick was pushed to this frame
+    pub stick: Option<Direction>,
+    stick_held: bool,
+}
+
+impl Gamepads {
+    pub fn connected(&self) -> &[Gamepad] {
+        &self.connected
+    }
+}
+
+fn connection_system(
+    mut gamepads: ResMut<Gamepads>,
+    (mut event_reader, events): (Local<EventReader<GamepadEvent>>, Res<Events<GamepadEvent>>),
+) {
+    for event in event_reader.iter(&events) {
+        match event {
+            GamepadEvent(gamepad, GamepadEventType::Connected) => {
+                info!("{:?} connected", gamepad);
+                gamepads.connected.push(*gamepad);
+            }
+            GamepadEvent(gamepad, GamepadEventType::Disconnected) => {
+                info!("{:?} disconnected", gamepad);
+                gamepads.connected.retain(|connected| connected != gamepad);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn stick_system(mut gamepads: ResMut<Gamepads>, axes: Res<Axis<GamepadAxis>>) {
+    let stick = gamepads.connected.iter().find_map(|gamepad| {
+        let x = axes
+            .get(GamepadAxis(*gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or(0.);
+        let y = axes
+            .get(GamepadAxis(*gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or(0.);
+        if x.abs().max(y.abs()) < STICK_THRESHOLD {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if y > 0. {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
+        }
+    });
+    // a stick moves once each time it is pushed, not every frame it is held
+    gamepads.stick = if gamepads.stick_held { None } else { stick };
+    gamepads.stick_held = stick.is_some();
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Gamepads>()
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, connection_system)
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, stick_system);
+    }
+}
@@ -0,0 +1,341 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::input::Action;
+
+const CURRENT_SCREEN: crate::Screen = crate::Screen::Highscores;
+
+pub const HIGHSCORES_FILE: &str = "highscores.conf";
+// entries kept in the table
+pub const MAX_ENTRIES: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub score: u32,
+    // time the match lasted, in seconds
+    pub time: f32,
+    pub outcome: crate::game::GameState,
+    // seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+    pub difficulty: crate::difficulty::Difficulty,
+}
+
+// Best matches played, highest score first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Highscores {
+    pub entries: Vec<Entry>,
+}
+
+impl Highscores {
+    fn path() -> String {
+        config::Layers::new(crate::APP_NAME, HIGHSCORES_FILE)
+            .user_file()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    pub fn load() -> Result<Highscores, config::Error> {
+        config::read_from(&Highscores::path())
+    }
+
+    pub fn save(&self) -> Result<(), config::Error> {
+        config::write_to(&Highscores::path(), self)
+    }
+
+    pub fn best(&self) -> u32 {
+        self.entries.first().map(|entry| entry.score).unwrap_or(0)
+    }
+
+    // rank of the entry in the table, starting at 0, or `None` if it didn't make it
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        // a new score goes after the ones it ties with
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| existing.score < entry.score)
+            .unwrap_or_else(|| self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// `YYYY-MM-DD` of a date in seconds since the unix epoch
+pub fn format_date(date: u64) -> String {
+    // from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (date / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub fn format_time(time: f32) -> String {
+    let secs = time.floor() as i32;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+struct ScreenTag;
+
+struct Screen {
+    loaded: bool,
+}
+impl Default for Screen {
+    fn default() -> Self {
+        Screen { loaded: false }
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Highscores>()
+            .add_resource(Screen::default())
+            .add_startup_system(load_highscores)
+            .add_system(input_system)
+            .add_system(setup)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+    }
+}
+
+fn load_highscores(mut highscores: ResMut<Highscores>, mut game_screen: ResMut<crate::GameScreen>) {
+    *highscores = Highscores::load().unwrap_or_else(|err| {
+        warn!("could not load highscores: {}", err);
+        Highscores::default()
+    });
+    game_screen.highscore = highscores.best();
+}
+
+const COLUMNS: &[(&str, f32)] = &[
+    ("", 50.),
+    ("score", 120.),
+    ("time", 100.),
+    ("outcome", 120.),
+    ("date", 160.),
+    ("difficulty", 140.),
+    ("seed", 250.),
+];
+
+fn setup(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    highscores: Res<Highscores>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
+        info!("Loading screen");
+
+        let color_none = materials.add(Color::NONE.into());
+
+        let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+        let font_sub: Handle<Font> = asset_handles.get_font_sub_handle(&asset_server);
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(10.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(5.),
+                    },
+                    size: Size::<Val> {
+                        height: Val::Px(75.),
+                        width: Val::Auto,
+                    },
+                    ..Default::default()
+                },
+                material: color_none.clone(),
+                ..Default::default()
+            })
+            .with_children(|title_parent| {
+                title_parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(75.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "Highscores".to_string(),
+                        font,
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT,
+                            font_size: 75.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+            })
+            .with(ScreenTag);
+
+        let header = COLUMNS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        let rows = highscores
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                vec![
+                    format!("{}", rank + 1),
+                    format!("{}", entry.score),
+                    format_time(entry.time),
+                    match entry.outcome {
+                        crate::game::GameState::Win => "won".to_string(),
+                        _ => "lost".to_string(),
+                    },
+                    format_date(entry.date),
+                    entry.difficulty.to_string(),
+                    format!("{}", entry.seed),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(10.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(20.),
+                    },
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..Default::default()
+                },
+                material: color_none.clone(),
+                ..Default::default()
+            })
+            .with(ScreenTag)
+            .with_children(|table_parent| {
+                for (i, row) in std::iter::once(&header).chain(rows.iter()).enumerate() {
+                    table_parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                margin: Rect {
+                                    bottom: Val::Px(10.),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            },
+                            material: color_none.clone(),
+                            ..Default::default()
+                        })
+                        .with_children(|row_parent| {
+                            for (cell, (_, width)) in row.iter().zip(COLUMNS.iter()) {
+                                row_parent.spawn(TextBundle {
+                                    style: Style {
+                                        size: Size {
+                                            width: Val::Px(*width),
+                                            height: Val::Px(25.),
+                                        },
+                                        ..Default::default()
+                                    },
+                                    text: Text {
+                                        value: cell.clone(),
+                                        font: font_sub.clone(),
+                                        style: TextStyle {
+                                            color: if i == 0 {
+                                                crate::ui::ColorScheme::TEXT_DIM
+                                            } else {
+                                                crate::ui::ColorScheme::TEXT
+                                            },
+                                            font_size: 25.,
+                                            ..Default::default()
+                                        },
+                                    },
+                                    ..Default::default()
+                                });
+                            }
+                        });
+                }
+                if rows.is_empty() {
+                    table_parent.spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(25.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: "no match played yet".to_string(),
+                            font: font_sub.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT_DIM,
+                                font_size: 25.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    });
+                }
+            });
+
+        screen.loaded = true;
+    }
+}
+
+fn tear_down(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    query: Query<Entity, With<ScreenTag>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN && screen.loaded {
+        info!("tear down");
+
+        for entity in query.iter() {
+            commands.despawn_recursive(entity);
+        }
+
+        screen.loaded = false;
+    }
+}
+
+fn input_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    screen: Res<Screen>,
+    actions: Res<crate::input::Actions>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN
+        && screen.loaded
+        && (actions.just_pressed(Action::Select)
+            || actions.just_released(Action::Back)
+            || actions.just_released(Action::Confirm))
+    {
+        game_screen.current_screen = crate::Screen::Menu;
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    input::gamepad::{GamepadButton, GamepadButtonType},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::gamepad::{Direction, Gamepads};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    // click on what is under the cursor
+    Select,
+    Send,
+    // select the moon being targeted
+    SelectTarget,
+    Deselect,
+    TargetPlanet,
+    CycleRatio,
+    CycleRatioBack,
+    SwitchShipClass,
+    ToggleFullscreen,
+    Back,
+    Confirm,
+    Up,
+    Down,
+    Left,
+    Right,
+    // move the camera while held
+    Pan,
+    ZoomIn,
+    ZoomOut,
+    SpeedUp,
+    SpeedDown,
+    // select the moon with this number
+    Moon1,
+    Moon2,
+    Moon3,
+    Moon4,
+    Moon5,
+    Moon6,
+    Moon7,
+    Moon8,
+    Moon9,
+    Moon10,
+    // held with a moon number to target the moon instead
+    TargetModifier,
+}
+
+impl Action {
+    pub const MOONS: [Action; 10] = [
+        Action::Moon1,
+        Action::Moon2,
+        Action::Moon3,
+        Action::Moon4,
+        Action::Moon5,
+        Action::Moon6,
+        Action::Moon7,
+        Action::Moon8,
+        Action::Moon9,
+        Action::Moon10,
+    ];
+
+    pub const ALL: [Action; 31] = [
+        Action::Select,
+        Action::Send,
+        Action::SelectTarget,
+        Action::Deselect,
+        Action::TargetPlanet,
+        Action::CycleRatio,
+        Action::CycleRatioBack,
+        Action::SwitchShipClass,
+        Action::ToggleFullscreen,
+        Action::Back,
+        Action::Confirm,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Pan,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::SpeedUp,
+        Action::SpeedDown,
+        Action::Moon1,
+        Action::Moon2,
+        Action::Moon3,
+        Action::Moon4,
+        Action::Moon5,
+        Action::Moon6,
+        Action::Moon7,
+        Action::Moon8,
+        Action::Moon9,
+        Action::Moon10,
+        Action::TargetModifier,
+    ];
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Select => write!(f, "Select"),
+            Action::Send => write!(f, "Send ships"),
+            Action::SelectTarget => write!(f, "Select target"),
+            Action::Deselect => write!(f, "Deselect"),
+            Action::TargetPlanet => write!(f, "Target planet"),
+            Action::CycleRatio => write!(f, "Next ratio"),
+            Action::CycleRatioBack => write!(f, "Previous ratio"),
+            Action::SwitchShipClass => write!(f, "Ship class"),
+            Action::ToggleFullscreen => write!(f, "Fullscreen"),
+            Action::Back => write!(f, "Back"),
+            Action::Confirm => write!(f, "Confirm"),
+            Action::Up => write!(f, "Up"),
+            Action::Down => write!(f, "Down"),
+            Action::Left => write!(f, "Left"),
+            Action::Right => write!(f, "Right"),
+            Action::Pan => write!(f, "Move camera"),
+            Action::ZoomIn => write!(f, "Zoom in"),
+            Action::ZoomOut => write!(f, "Zoom out"),
+            Action::SpeedUp => write!(f, "Faster"),
+            Action::SpeedDown => write!(f, "Slower"),
+            Action::Moon1 => write!(f, "Moon 1"),
+            Action::Moon2 => write!(f, "Moon 2"),
+            Action::Moon3 => write!(f, "Moon 3"),
+            Action::Moon4 => write!(f, "Moon 4"),
+            Action::Moon5 => write!(f, "Moon 5"),
+            Action::Moon6 => write!(f, "Moon 6"),
+            Action::Moon7 => write!(f, "Moon 7"),
+            Action::Moon8 => write!(f, "Moon 8"),
+            Action::Moon9 => write!(f, "Moon 9"),
+            Action::Moon10 => write!(f, "Moon 10"),
+            Action::TargetModifier => write!(f, "Target moon"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(button) => write!(f, "Mouse {:?}", button),
+            Binding::Gamepad(button) => write!(f, "Pad {:?}", button),
+        }
+    }
+}
+
+impl Binding {
+    // a binding replaces the one of the same device when rebinding
+    pub fn same_device(&self, other: &Binding) -> bool {
+        matches!(
+            (self, other),
+            (Binding::Key(_), Binding::Key(_))
+                | (Binding::Mouse(_), Binding::Mouse(_))
+                | (Binding::Gamepad(_), Binding::Gamepad(_))
+        )
+    }
+}
+
+const MOON_KEYS: [KeyCode; 10] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "HashMap<Action, Vec<Binding>>")]
+pub struct Bindings(pub HashMap<Action, Vec<Binding>>);
+
+// actions added since the settings were saved keep their default bindings
+impl From<HashMap<Action, Vec<Binding>>> for Bindings {
+    fn from(saved: HashMap<Action, Vec<Binding>>) -> Self {
+        let mut bindings = Bindings::default();
+        bindings.0.extend(saved);
+        bindings
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let bindings = vec![
+            (Action::Select, vec![Binding::Mouse(MouseButton::Left)]),
+            (
+                Action::Send,
+                vec![
+                    Binding::Mouse(MouseButton::Right),
+                    Binding::Key(KeyCode::Space),
+                    Binding::Gamepad(GamepadButtonType::West),
+                ],
+            ),
+            (
+                Action::SelectTarget,
+                vec![
+                    Binding::Key(KeyCode::Return),
+                    Binding::Gamepad(GamepadButtonType::South),
+                ],
+            ),
+            (
+                Action::Deselect,
+                vec![
+                    Binding::Key(KeyCode::Back),
+                    Binding::Gamepad(GamepadButtonType::East),
+                ],
+            ),
+            (Action::TargetPlanet, vec![Binding::Key(KeyCode::P)]),
+            (
+                Action::CycleRatio,
+                vec![
+                    Binding::Key(KeyCode::Tab),
+                    Binding::Gamepad(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (
+                Action::CycleRatioBack,
+                vec![Binding::Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (
+                Action::SwitchShipClass,
+                vec![
+                    Binding::Key(KeyCode::C),
+                    Binding::Gamepad(GamepadButtonType::North),
+                ],
+            ),
+            (Action::ToggleFullscreen, vec![Binding::Key(KeyCode::F)]),
+            (
+                Action::Back,
+                vec![
+                    Binding::Key(KeyCode::Escape),
+                    Binding::Gamepad(GamepadButtonType::Start),
+                ],
+            ),
+            (
+                Action::Confirm,
+                vec![
+                    Binding::Key(KeyCode::Space),
+                    Binding::Key(KeyCode::Return),
+                    Binding::Gamepad(GamepadButtonType::South),
+                ],
+            ),
+            (
+                Action::Up,
+                vec![
+                    Binding::Key(KeyCode::Up),
+                    Binding::Gamepad(GamepadButtonType::DPadUp),
+                ],
+            ),
+            (
+                Action::Down,
+                vec![
+                    Binding::Key(KeyCode::Down),
+                    Binding::Gamepad(GamepadButtonType::DPadDown),
+                ],
+            ),
+            (
+                Action::Left,
+                vec![
+                    Binding::Key(KeyCode::Left),
+                    Binding::Gamepad(GamepadButtonType::DPadLeft),
+                ],
+            ),
+            (
+                Action::Right,
+                vec![
+                    Binding::Key(KeyCode::Right),
+                    Binding::Gamepad(GamepadButtonType::DPadRight),
+                ],
+            ),
+            (Action::Pan, vec![Binding::Mouse(MouseButton::Middle)]),
+            (
+                Action::ZoomIn,
+                vec![
+                    Binding::Key(KeyCode::Equals),
+                    Binding::Gamepad(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                Action::ZoomOut,
+                vec![
+                    Binding::Key(KeyCode::Minus),
+                    Binding::Gamepad(GamepadButtonType::LeftTrigger2),
+                ],
+            ),
+            (Action::SpeedUp, vec![Binding::Key(KeyCode::RBracket)]),
+            (Action::SpeedDown, vec![Binding::Key(KeyCode::LBracket)]),
+            (
+                Action::TargetModifier,
+                vec![Binding::Key(KeyCode::LShift), Binding::Key(KeyCode::RShift)],
+            ),
+        ];
+        let moons = Action::MOONS
+            .iter()
+            .zip(MOON_KEYS.iter())
+            .map(|(action, key)| (*action, vec![Binding::Key(*key)]));
+        Bindings(bindings.into_iter().chain(moons).collect())
+    }
+}
+
+impl Bindings {
+    pub fn of(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_insert_with(Vec::new);
+        bindings.retain(|bound| !bound.same_device(&binding));
+        bindings.push(binding);
+    }
+}
+
+// State of each action this frame, from the bindings in `Settings`. Systems should read
+// this instead of the keyboard, mouse or gamepad directly.
+#[derive(Default)]
+pub struct Actions {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+}
+
+impl Actions {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+}
+
+fn update_actions(
+    mut actions: ResMut<Actions>,
+    settings: Res<crate::Settings>,
+    gamepads: Res<Gamepads>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    actions.pressed.clear();
+    actions.just_pressed.clear();
+    actions.just_released.clear();
+    for action in Action::ALL.iter() {
+        for binding in settings.bindings.of(*action) {
+            let (pressed, just_pressed, just_released) = match binding {
+                Binding::Key(key) => (
+                    keyboard_input.pressed(*key),
+                    keyboard_input.just_pressed(*key),
+                    keyboard_input.just_released(*key),
+                ),
+                Binding::Mouse(button) => (
+                    mouse_button_input.pressed(*button),
+                    mouse_button_input.just_pressed(*button),
+                    mouse_button_input.just_released(*button),
+                ),
+                Binding::Gamepad(button) => gamepads.connected().iter().fold(
+                    (false, false, false),
+                    |(pressed, just_pressed, just_released), gamepad| {
+                        let button = GamepadButton(*gamepad, *button);
+                        (
+                            pressed || gamepad_buttons.pressed(button),
+                            just_pressed || gamepad_buttons.just_pressed(button),
+                            just_released || gamepad_buttons.just_released(button),
+                        )
+                    },
+                ),
+            };
+            if pressed {
+                actions.pressed.insert(*action);
+            }
+            if just_pressed {
+                actions.just_pressed.insert(*action);
+            }
+            if just_released {
+                actions.just_released.insert(*action);
+            }
+        }
+    }
+
+    // pushing a stick is a press and a release of the direction at once
+    if let Some(direction) = gamepads.stick {
+        let action = match direction {
+            Direction::Up => Action::Up,
+            Direction::Down => Action::Down,
+            Direction::Left => Action::Left,
+            Direction::Right => Action::Right,
+        };
+        actions.just_pressed.insert(action);
+        actions.just_released.insert(action);
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Actions>()
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, update_actions);
+    }
+}
@@ -0,0 +1,340 @@
+use bevy::prelude::*;
+use tracing::info;
+
+use crate::input::Action;
+
+const CURRENT_SCREEN: crate::Screen = crate::Screen::LevelSelect;
+
+struct ScreenTag;
+
+struct Screen {
+    loaded: bool,
+    selected: Option<usize>,
+}
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            loaded: false,
+            selected: None,
+        }
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Screen::default())
+            .add_system(setup)
+            .add_system(keyboard_input_system)
+            .add_system(button_system)
+            .add_system(display_selection)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LevelButton(usize);
+
+impl Into<String> for LevelButton {
+    fn into(self) -> String {
+        format!("Level {}", self.0 + 1)
+    }
+}
+
+struct LevelName(usize);
+
+fn setup(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    campaign: Res<crate::campaign::Campaign>,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    asset_server: Res<AssetServer>,
+    mut nine_patches: ResMut<Assets<bevy_ninepatch::NinePatchBuilder<()>>>,
+    mut buttons: ResMut<Assets<crate::ui::button::Button>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
+        info!("Loading screen");
+
+        let button_handle = asset_handles.get_button_handle(
+            &asset_server,
+            &mut materials,
+            &mut nine_patches,
+            &mut buttons,
+        );
+        let button = buttons.get(&button_handle).unwrap();
+
+        let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+        let font_sub: Handle<Font> = asset_handles.get_font_sub_handle(&asset_server);
+
+        let color_none = materials.add(Color::NONE.into());
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(20.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(15.),
+                    },
+                    size: Size::<Val> {
+                        height: Val::Px(75.),
+                        width: Val::Auto,
+                    },
+                    ..Default::default()
+                },
+                material: color_none.clone(),
+                ..Default::default()
+            })
+            .with_children(|title_parent| {
+                title_parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(75.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "Campaign".to_string(),
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT,
+                            font_size: 75.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+            })
+            .with(ScreenTag);
+
+        let rows = campaign
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                let row = commands
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            margin: Rect {
+                                bottom: Val::Px(10.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        material: color_none.clone(),
+                        ..Default::default()
+                    })
+                    .current_entity()
+                    .unwrap();
+                let unlocked = campaign.is_unlocked(i);
+                let label = if unlocked {
+                    button.add(
+                        commands,
+                        150.,
+                        40.,
+                        Rect::all(Val::Auto),
+                        font.clone(),
+                        LevelButton(i),
+                        20.,
+                    )
+                } else {
+                    commands
+                        .spawn(TextBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(150.), Val::Px(20.)),
+                                ..Default::default()
+                            },
+                            text: Text {
+                                value: LevelButton(i).into(),
+                                font: font.clone(),
+                                style: TextStyle {
+                                    color: crate::ui::ColorScheme::TEXT_DIM,
+                                    font_size: 20.,
+                                    ..Default::default()
+                                },
+                            },
+                            ..Default::default()
+                        })
+                        .current_entity()
+                        .unwrap()
+                };
+                commands.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(20.),
+                            ..Default::default()
+                        },
+                        margin: Rect {
+                            left: Val::Px(20.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: if unlocked {
+                            format!("{}: {}", level.name, level.win)
+                        } else {
+                            "locked".to_string()
+                        },
+                        font: font_sub.clone(),
+                        style: TextStyle {
+                            color: if unlocked {
+                                crate::ui::ColorScheme::TEXT
+                            } else {
+                                crate::ui::ColorScheme::TEXT_DIM
+                            },
+                            font_size: 20.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+                if unlocked {
+                    commands.with(LevelName(i));
+                }
+                let name = commands.current_entity().unwrap();
+                commands.push_children(row, &[label, name]);
+                row
+            })
+            .collect::<Vec<_>>();
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(20.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(30.),
+                    },
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..Default::default()
+                },
+                material: color_none,
+                ..Default::default()
+            })
+            .with(ScreenTag);
+        let list = commands.current_entity().unwrap();
+        commands.push_children(list, rows.as_slice());
+
+        screen.selected = None;
+        screen.loaded = true;
+    }
+}
+
+fn tear_down(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    query: Query<Entity, With<ScreenTag>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN && screen.loaded {
+        info!("tear down");
+
+        for entity in query.iter() {
+            commands.despawn_recursive(entity);
+        }
+
+        screen.loaded = false;
+    }
+}
+
+fn start_level(
+    level: usize,
+    game_screen: &mut crate::GameScreen,
+    (campaign, config, map, match_start): (
+        &mut crate::campaign::Campaign,
+        &mut crate::Config,
+        &mut crate::game::map::MapDefinition,
+        &mut crate::game::MatchStart,
+    ),
+) {
+    if campaign.is_unlocked(level) {
+        campaign.start(level, config, map);
+        *match_start = crate::game::MatchStart::New;
+        game_screen.current_screen = crate::Screen::Game;
+    }
+}
+
+fn keyboard_input_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    actions: Res<crate::input::Actions>,
+    (mut campaign, mut config, mut map, mut match_start): (
+        ResMut<crate::campaign::Campaign>,
+        ResMut<crate::Config>,
+        ResMut<crate::game::map::MapDefinition>,
+        ResMut<crate::game::MatchStart>,
+    ),
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && screen.loaded {
+        let unlocked = (0..campaign.levels.len())
+            .filter(|level| campaign.is_unlocked(*level))
+            .count();
+        if actions.just_released(Action::Back) {
+            game_screen.current_screen = crate::Screen::Menu;
+        } else if actions.just_released(Action::Down) {
+            screen.selected = Some(
+                screen
+                    .selected
+                    .map(|i| usize::min(unlocked.max(1) - 1, i + 1))
+                    .unwrap_or(0),
+            );
+        } else if actions.just_released(Action::Up) {
+            screen.selected = Some(screen.selected.map(|i| i.saturating_sub(1)).unwrap_or(0));
+        } else if actions.just_released(Action::Confirm) {
+            if let Some(level) = screen.selected {
+                start_level(
+                    level,
+                    &mut game_screen,
+                    (&mut campaign, &mut config, &mut map, &mut match_start),
+                );
+            }
+        }
+    }
+}
+
+fn button_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    (mut campaign, mut config, mut map, mut match_start): (
+        ResMut<crate::campaign::Campaign>,
+        ResMut<crate::Config>,
+        ResMut<crate::game::map::MapDefinition>,
+        ResMut<crate::game::MatchStart>,
+    ),
+    mut interaction_query: Query<
+        (&Interaction, &crate::ui::button::ButtonId<LevelButton>),
+        (With<Button>, Mutated<Interaction>),
+    >,
+) {
+    for (interaction, button_id) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => start_level(
+                (button_id.0).0,
+                &mut game_screen,
+                (&mut campaign, &mut config, &mut map, &mut match_start),
+            ),
+            Interaction::Hovered => screen.selected = Some((button_id.0).0),
+            Interaction::None => screen.selected = None,
+        }
+    }
+}
+
+fn display_selection(screen: Res<Screen>, mut query: Query<(&LevelName, &mut Text)>) {
+    for (level, mut text) in query.iter_mut() {
+        text.style.color = if screen.selected == Some(level.0) {
+            crate::ui::ColorScheme::TEXT_HIGHLIGHT
+        } else {
+            crate::ui::ColorScheme::TEXT
+        };
+    }
+}
@@ -0,0 +1,319 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use serde::{Deserialize, Serialize};
+
+mod assets;
+pub mod ui;
+pub use assets::AssetHandles;
+
+mod about;
+pub mod campaign;
+pub mod clock;
+mod controls;
+pub mod difficulty;
+mod end;
+pub mod game;
+mod gamepad;
+pub mod highscores;
+pub mod input;
+mod level_select;
+mod menu;
+pub mod reload;
+mod render;
+pub mod replay;
+pub mod rng;
+pub mod settings;
+pub mod space;
+mod splash;
+
+pub const Z_PLANET: f32 = 0.0;
+pub const Z_MOON: f32 = 1.0;
+pub const Z_SHIP: f32 = 2.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Config {
+    pub bigger_player_ship_rate: f64,
+    pub bigger_player_ship_change: f32,
+    pub fleet_timer: f32,
+    pub fleet_delay: f32,
+    pub fleet_chance: f32,
+    pub asteroid_timer: f32,
+    pub asteroid_chance: f32,
+    pub missile_timer: f32,
+    pub missile_range: f32,
+    pub planet_missile_timer: f32,
+    // chance of a fleet once all moons are freed
+    pub fleet_override_chance: f32,
+    pub fleet_min_health: i32,
+    // the bigger, the smaller the fleets
+    pub fleet_health_divisor: f32,
+    // ships gathering around a moon before the planet sends them at the player
+    pub swarm_size: usize,
+    // one of the ratios the player sends ships with, so 75% by default where 80% were sent
+    // before the planet had a strategy
+    pub swarm_ratio: game::Ratio,
+    // make the planet harder or easier depending on how the player does, within bounds
+    pub adaptive_difficulty: bool,
+    pub adaptive_min: f32,
+    pub adaptive_max: f32,
+    // used for rival factions the map doesn't give a strategy to
+    pub rival_strategy: game::ai::Strategy,
+    pub planet_strategy: game::ai::Strategy,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            bigger_player_ship_rate: 0.75,
+            bigger_player_ship_change: 1.25,
+            fleet_timer: 2.,
+            fleet_delay: 6.,
+            fleet_chance: 0.4,
+            asteroid_timer: 30.,
+            asteroid_chance: 0.5,
+            missile_timer: 4.,
+            missile_range: 150.,
+            planet_missile_timer: 10.,
+            fleet_override_chance: 0.75,
+            fleet_min_health: -4,
+            fleet_health_divisor: 1.5,
+            swarm_size: 25,
+            swarm_ratio: game::Ratio::ThreeQuarter,
+            adaptive_difficulty: false,
+            adaptive_min: 0.5,
+            adaptive_max: 1.5,
+            rival_strategy: game::ai::Strategy::Opportunist,
+            planet_strategy: game::ai::Strategy::Swarm,
+        }
+    }
+}
+
+// Values to change in a `Config`, everything left out is kept.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub bigger_player_ship_rate: Option<f64>,
+    pub bigger_player_ship_change: Option<f32>,
+    pub fleet_timer: Option<f32>,
+    pub fleet_delay: Option<f32>,
+    pub fleet_chance: Option<f32>,
+    pub asteroid_timer: Option<f32>,
+    pub asteroid_chance: Option<f32>,
+    pub missile_timer: Option<f32>,
+    pub missile_range: Option<f32>,
+    pub planet_missile_timer: Option<f32>,
+    pub fleet_override_chance: Option<f32>,
+    pub fleet_min_health: Option<i32>,
+    pub fleet_health_divisor: Option<f32>,
+    pub swarm_size: Option<usize>,
+    pub swarm_ratio: Option<game::Ratio>,
+    pub adaptive_difficulty: Option<bool>,
+    pub adaptive_min: Option<f32>,
+    pub adaptive_max: Option<f32>,
+    pub rival_strategy: Option<game::ai::Strategy>,
+    pub planet_strategy: Option<game::ai::Strategy>,
+}
+
+impl ConfigOverrides {
+    // Fields are matched by name, the ones left out are null and keep the value of `config`.
+    pub fn apply(&self, config: &mut Config) {
+        let mut merged = serde_json::to_value(&*config).expect("a config can be serialized");
+        let overrides = serde_json::to_value(self).expect("overrides can be serialized");
+        if let (Some(merged), serde_json::Value::Object(overrides)) =
+            (merged.as_object_mut(), overrides)
+        {
+            merged.extend(overrides.into_iter().filter(|(_, value)| !value.is_null()));
+        }
+        *config = serde_json::from_value(merged).expect("overrides have the types of the config");
+    }
+}
+
+// name of the configuration directories and prefix of the environment variables
+pub const APP_NAME: &str = "wotm";
+pub const CONFIG_FILE: &str = "config.conf";
+pub const SETTINGS_FILE: &str = "settings.conf";
+
+// Where the `ConfigOverrides` are read from, see `config::Layers` for the precedence.
+pub fn config_layers(explicit: Option<String>) -> config::Layers {
+    config::Layers::new(APP_NAME, CONFIG_FILE).with_explicit(explicit)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WindowMode {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl WindowMode {
+    pub const ALL: [WindowMode; 3] = [
+        WindowMode::Windowed,
+        WindowMode::Borderless,
+        WindowMode::Fullscreen,
+    ];
+}
+
+impl std::fmt::Display for WindowMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowMode::Windowed => write!(f, "windowed"),
+            WindowMode::Borderless => write!(f, "borderless fullscreen"),
+            WindowMode::Fullscreen => write!(f, "fullscreen"),
+        }
+    }
+}
+
+impl From<WindowMode> for bevy::window::WindowMode {
+    fn from(mode: WindowMode) -> Self {
+        match mode {
+            WindowMode::Windowed => bevy::window::WindowMode::Windowed,
+            WindowMode::Borderless => bevy::window::WindowMode::BorderlessFullscreen,
+            WindowMode::Fullscreen => bevy::window::WindowMode::Fullscreen { use_size: true },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Settings {
+    pub width: u32,
+    pub height: u32,
+    pub window_mode: WindowMode,
+    pub vsync: bool,
+    // between 0 and 1, the game has no sound yet
+    pub volume: f32,
+    pub difficulty: difficulty::Difficulty,
+    pub bindings: input::Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            width: 1280,
+            height: 720,
+            window_mode: WindowMode::Windowed,
+            vsync: true,
+            volume: 1.,
+            difficulty: difficulty::Difficulty::Normal,
+            bindings: input::Bindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn layers(explicit: Option<String>) -> config::Layers {
+        config::Layers::new(APP_NAME, SETTINGS_FILE).with_explicit(explicit)
+    }
+
+    // to the user file, so that it comes before the other settings files the next time
+    pub fn save(&self) -> Result<(), config::Error> {
+        config::write_to(&Settings::layers(None).user_file().to_string_lossy(), self)
+    }
+}
+
+pub mod custom_stage {
+    pub const TEAR_DOWN: &str = "kmanb:tear_down";
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Screen {
+    Splash,
+    Menu,
+    About,
+    LevelSelect,
+    Highscores,
+    Settings,
+    Controls,
+    Game,
+    Exit,
+    End,
+}
+
+#[derive(Debug)]
+pub struct GameScreen {
+    pub current_screen: Screen,
+    // best score of the highscores
+    pub highscore: u32,
+}
+
+impl Default for GameScreen {
+    fn default() -> Self {
+        GameScreen {
+            current_screen: Screen::Splash,
+            highscore: 0,
+        }
+    }
+}
+
+impl GameScreen {
+    pub fn is_new_highscore(&self, score: u32) -> bool {
+        self.highscore != 0 && score > self.highscore
+    }
+}
+
+struct SetupPlugin;
+impl Plugin for SetupPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameScreen>()
+            .init_resource::<Config>()
+            .init_resource::<Settings>()
+            .init_resource::<difficulty::ConfigLayers>()
+            .init_resource::<rng::MatchRng>()
+            .init_resource::<clock::GameClock>()
+            .add_stage_after(bevy::app::stage::UPDATE, custom_stage::TEAR_DOWN)
+            .add_startup_system(physics_setup)
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, clock::tick_clock)
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, clock::step_physics);
+    }
+}
+
+fn physics_setup(mut configuration: ResMut<bevy_rapier2d::physics::RapierConfiguration>) {
+    configuration.gravity = bevy_rapier2d::rapier::math::Vector::new(0., 0.);
+}
+
+fn add_simulation_plugins(group: &mut PluginGroupBuilder) {
+    group
+        .add(SetupPlugin)
+        .add(bevy_rapier2d::physics::RapierPhysicsPlugin)
+        .add(crate::game::SimulationPlugin)
+        .add(crate::space::Plugin)
+        .add(crate::replay::Plugin)
+        .add(crate::campaign::Plugin);
+}
+
+/// Match rules, without anything needing a window or a GPU.
+pub struct SimulationPlugins;
+impl PluginGroup for SimulationPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        add_simulation_plugins(group);
+    }
+}
+
+/// Match rules for a headless app, to add after `MinimalPlugins`.
+///
+/// Set `GameScreen::current_screen` to `Screen::Game` to start a match.
+pub struct HeadlessPlugins;
+impl PluginGroup for HeadlessPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group.add(bevy::transform::TransformPlugin);
+        add_simulation_plugins(group);
+    }
+}
+
+/// Screens, ui and sprites for the simulation entities, to add after `SimulationPlugins`.
+pub struct ScreenPlugins;
+impl PluginGroup for ScreenPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(crate::gamepad::Plugin)
+            .add(crate::input::Plugin)
+            .add(crate::ui::button::Plugin)
+            .add(crate::render::Plugin)
+            .add(crate::splash::Plugin)
+            .add(crate::menu::Plugin)
+            .add(crate::about::Plugin)
+            .add(crate::level_select::Plugin)
+            .add(crate::highscores::Plugin)
+            .add(crate::settings::Plugin)
+            .add(crate::controls::Plugin)
+            .add(crate::game::Plugin)
+            .add(crate::end::Plugin);
+    }
+}
@@ -1,73 +1,40 @@
 // disable console opening on windows
 #![windows_subsystem = "windows"]
 
-use bevy::{app::AppExit, prelude::*, window::WindowMode};
-use serde::{Deserialize, Serialize};
-
-mod assets;
-pub mod ui;
-use assets::AssetHandles;
-
-mod about;
-mod end;
-mod game;
-mod menu;
-mod space;
-mod splash;
-
-pub const Z_PLANET: f32 = 0.0;
-pub const Z_MOON: f32 = 1.0;
-pub const Z_SHIP: f32 = 2.0;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Settings {
-    width: u32,
-    height: u32,
-    fullscreen: bool,
-}
-
-impl Default for Settings {
-    fn default() -> Settings {
-        Settings {
-            width: 1280,
-            height: 720,
-            fullscreen: false,
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Config {
-    pub bigger_player_ship_rate: f64,
-    pub bigger_player_ship_change: f32,
-    pub fleet_timer: f32,
-    pub fleet_delay: f32,
-    pub fleet_chance: f32,
-    pub asteroid_timer: f32,
-    pub asteroid_chance: f32,
-}
-
-impl Default for Config {
-    fn default() -> Config {
-        Config {
-            bigger_player_ship_rate: 0.75,
-            bigger_player_ship_change: 1.25,
-            fleet_timer: 2.,
-            fleet_delay: 6.,
-            fleet_chance: 0.4,
-            asteroid_timer: 30.,
-            asteroid_chance: 0.5,
-        }
-    }
-}
+use bevy::{app::AppExit, prelude::*};
+use tracing::warn;
 
-use lazy_static::lazy_static;
-lazy_static! {
-    static ref CONFIG: Config = config::read_from("config.conf").unwrap();
-}
+use wotm::{
+    clock::GameClock, difficulty::ConfigLayers, game::map::MapDefinition, reload::ConfigWatcher,
+    replay, rng::MatchRng, ConfigOverrides, GameScreen, Screen, Settings,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let settings: Settings = config::read_from("settings.conf")?;
+    // a broken settings or config file is reported once logging is set up, the game starts with
+    // the defaults for that file, or doesn't start at all with `--strict-config`
+    let (settings, settings_error) =
+        Settings::layers(arg_value("--settings")).read_or_default::<Settings>();
+    let explicit = arg_value("--config");
+    let config_watcher = ConfigWatcher::new(wotm::config_layers(explicit.clone()));
+    let (user, user_error) = wotm::config_layers(explicit).read_or_default::<ConfigOverrides>();
+    let mut config_errors = settings_error
+        .into_iter()
+        .chain(user_error)
+        .collect::<Vec<_>>();
+    if std::env::args().any(|arg| arg == "--strict-config") && !config_errors.is_empty() {
+        return Err(config_errors.remove(0).into());
+    }
+    let config_layers = ConfigLayers {
+        difficulty: settings.difficulty,
+        user,
+    };
+    let config = config_layers.config();
+    let seed = arg_value("--seed")
+        .map(|seed| seed.parse::<u64>())
+        .transpose()?;
+    let map = arg_value("--map")
+        .map(|path| MapDefinition::load(&path))
+        .transpose()?;
 
     let mut builder = App::build();
 
@@ -77,18 +44,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             title: "wotm".to_string(),
             width: settings.width,
             height: settings.height,
-            vsync: true,
+            vsync: settings.vsync,
             resizable: false,
-            mode: if settings.fullscreen {
-                WindowMode::Fullscreen { use_size: true }
-            } else {
-                WindowMode::Windowed
-            },
+            mode: settings.window_mode.into(),
             ..Default::default()
         })
+        .add_resource(config.clone())
+        .add_resource(config_layers)
+        .add_resource(config_watcher)
         .add_resource(settings)
         .add_resource(ClearColor(Color::rgb(0., 0., 0.01)));
 
+    if let Some(seed) = seed {
+        builder.add_resource(MatchRng::with_seed(Some(seed)));
+    }
+
+    if let Some(map) = map.clone() {
+        builder.add_resource(map);
+    }
+
+    if let Some(path) = arg_value("--replay") {
+        let replay = replay::Replay::load(&path)?;
+        builder
+            .add_resource(MatchRng::with_seed(Some(replay.seed)))
+            .add_resource(replay.config.clone())
+            .add_resource(replay.map.clone())
+            .add_resource(GameClock::scripted(replay.deltas.clone()))
+            .add_resource(GameScreen {
+                current_screen: Screen::Game,
+                ..Default::default()
+            })
+            .add_resource(replay::ReplayState::playback(
+                replay,
+                replay::BeforeReplay {
+                    fixed_seed: seed,
+                    config,
+                    map: map.unwrap_or_default(),
+                },
+            ));
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     if cfg!(debug_assertions) {
         builder.add_resource(bevy::log::LogSettings {
@@ -114,6 +109,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         group
     });
 
+    for err in config_errors.iter() {
+        warn!("{}, using the defaults instead", err);
+    }
+
     #[cfg(target_arch = "wasm32")]
     builder.add_plugin(bevy_webgl2::WebGL2Plugin::default());
 
@@ -139,72 +138,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // game management
         .add_startup_system(general_setup)
         .add_system(handle_state)
-        .add_resource(GameScreen::default())
-        .add_resource(Config::default())
-        .add_stage_after(bevy::app::stage::UPDATE, custom_stage::TEAR_DOWN)
-        // ui
-        .add_plugin(crate::ui::button::Plugin)
-        .add_resource(AssetHandles::default())
-        // collisions
-        .add_plugin(bevy_rapier2d::physics::RapierPhysicsPlugin)
+        .add_system(wotm::reload::watch_config)
+        // match rules and collisions
+        .add_plugins(wotm::SimulationPlugins)
         // screens
-        .add_plugin(crate::splash::Plugin)
-        .add_plugin(crate::menu::Plugin)
-        .add_plugin(crate::about::Plugin)
-        .add_plugin(crate::game::Plugin)
-        .add_plugin(crate::space::Plugin)
-        .add_plugin(crate::end::Plugin)
+        .add_plugins(wotm::ScreenPlugins)
         .run();
 
     Ok(())
 }
 
-pub mod custom_stage {
-    pub const TEAR_DOWN: &str = "kmanb:tear_down";
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub enum Screen {
-    Splash,
-    Menu,
-    About,
-    Game,
-    Exit,
-    End,
-}
-
-#[derive(Debug)]
-pub struct GameScreen {
-    pub current_screen: Screen,
-    pub highscore: u32,
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
 }
 
-impl Default for GameScreen {
-    fn default() -> Self {
-        GameScreen {
-            current_screen: Screen::Splash,
-            highscore: 0,
-        }
-    }
-}
-
-impl GameScreen {
-    pub fn is_new_highscore(&self, score: u32) -> bool {
-        self.highscore != 0 && score > self.highscore
-    }
-}
-
-fn general_setup(
-    commands: &mut Commands,
-    mut configuration: ResMut<bevy_rapier2d::physics::RapierConfiguration>,
-) {
-    configuration.gravity = bevy_rapier2d::rapier::math::Vector::new(0., 0.);
-
+fn general_setup(commands: &mut Commands) {
     commands.spawn(Camera2dBundle::default());
     commands.spawn(UiCameraBundle::default());
 }
 
-fn handle_state(game_screen: Res<crate::GameScreen>, mut app_exit_events: ResMut<Events<AppExit>>) {
+fn handle_state(game_screen: Res<GameScreen>, mut app_exit_events: ResMut<Events<AppExit>>) {
     if game_screen.current_screen == Screen::Exit {
         app_exit_events.send(AppExit);
     }
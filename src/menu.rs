@@ -4,6 +4,8 @@ use rand::Rng;
 
 use tracing::info;
 
+use crate::input::Action;
+
 use bevy_easings::Ease;
 
 const CURRENT_SCREEN: crate::Screen = crate::Screen::Menu;
@@ -14,6 +16,7 @@ struct Screen {
     loaded: bool,
     first_load: bool,
     menu_selected: Option<i32>,
+    buttons: Vec<MenuButton>,
 }
 impl Default for Screen {
     fn default() -> Self {
@@ -21,6 +24,7 @@ impl Default for Screen {
             loaded: false,
             first_load: true,
             menu_selected: None,
+            buttons: vec![],
         }
     }
 }
@@ -33,6 +37,7 @@ impl bevy::app::Plugin for Plugin {
             .add_system(setup)
             .add_system(button_system)
             .add_system(display_menu_item_selector)
+            .add_system(display_difficulty)
             .add_system(rotate_on_self)
             .add_system(go_away)
             .add_system(despawn_gone_ships)
@@ -41,9 +46,14 @@ impl bevy::app::Plugin for Plugin {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 enum MenuButton {
+    Continue,
     NewGame,
+    Campaign,
+    Difficulty,
+    Settings,
+    Highscores,
     About,
     #[cfg(not(target_arch = "wasm32"))]
     Quit,
@@ -52,7 +62,12 @@ enum MenuButton {
 impl Into<String> for MenuButton {
     fn into(self) -> String {
         match self {
+            MenuButton::Continue => "Continue".to_string(),
             MenuButton::NewGame => "New Game".to_string(),
+            MenuButton::Campaign => "Campaign".to_string(),
+            MenuButton::Difficulty => "Difficulty".to_string(),
+            MenuButton::Settings => "Settings".to_string(),
+            MenuButton::Highscores => "Highscores".to_string(),
             MenuButton::About => "About".to_string(),
             #[cfg(not(target_arch = "wasm32"))]
             MenuButton::Quit => "Quit".to_string(),
@@ -62,11 +77,57 @@ impl Into<String> for MenuButton {
 
 const MENU_BUTTONS: &[MenuButton] = &[
     MenuButton::NewGame,
+    MenuButton::Campaign,
+    MenuButton::Difficulty,
+    MenuButton::Settings,
+    MenuButton::Highscores,
     MenuButton::About,
     #[cfg(not(target_arch = "wasm32"))]
     MenuButton::Quit,
 ];
 
+fn menu_buttons() -> Vec<MenuButton> {
+    let mut buttons = vec![];
+    if crate::game::save::has_saved_match() {
+        buttons.push(MenuButton::Continue);
+    }
+    buttons.extend_from_slice(MENU_BUTTONS);
+    buttons
+}
+
+struct DifficultyLabel;
+
+fn select(
+    button: MenuButton,
+    game_screen: &mut crate::GameScreen,
+    match_start: &mut crate::game::MatchStart,
+    (config_layers, config): (&mut crate::difficulty::ConfigLayers, &mut crate::Config),
+    settings: &mut crate::Settings,
+) {
+    match button {
+        MenuButton::Continue => {
+            *match_start = crate::game::MatchStart::Resume;
+            game_screen.current_screen = crate::Screen::Game;
+        }
+        MenuButton::NewGame => {
+            *match_start = crate::game::MatchStart::New;
+            game_screen.current_screen = crate::Screen::Game;
+        }
+        MenuButton::Campaign => game_screen.current_screen = crate::Screen::LevelSelect,
+        MenuButton::Difficulty => {
+            config_layers.difficulty = config_layers.difficulty.next();
+            *config = config_layers.config();
+            settings.difficulty = config_layers.difficulty;
+            crate::settings::save(settings);
+        }
+        MenuButton::Settings => game_screen.current_screen = crate::Screen::Settings,
+        MenuButton::Highscores => game_screen.current_screen = crate::Screen::Highscores,
+        MenuButton::About => game_screen.current_screen = crate::Screen::About,
+        #[cfg(not(target_arch = "wasm32"))]
+        MenuButton::Quit => game_screen.current_screen = crate::Screen::Exit,
+    }
+}
+
 struct RotateOnSelf {
     duration: f64,
     offset: f64,
@@ -93,6 +154,7 @@ fn setup(
     mut buttons: ResMut<Assets<crate::ui::button::Button>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut atlases: ResMut<Assets<TextureAtlas>>,
+    config_layers: Res<crate::difficulty::ConfigLayers>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
         info!("Loading screen");
@@ -143,7 +205,7 @@ fn setup(
             .with(
                 crate::space::SpawnShip::every(7., crate::space::RotationDirection::Clockwise)
                     .with_scale(2.)
-                    .with_headstart(),
+                    .with_headstart(0.75),
             )
             .with(crate::game::OwnedBy::Neutral)
             .with(ScreenTag);
@@ -188,9 +250,32 @@ fn setup(
                     },
                     ..Default::default()
                 });
+                title_parent
+                    .spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(20.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: format!("Difficulty: {}", config_layers.difficulty),
+                            font: font.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT_DIM,
+                                font_size: 20.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    })
+                    .with(DifficultyLabel);
             })
             .with(ScreenTag);
 
+        screen.buttons = menu_buttons();
+
         let panel_style = Style {
             position_type: PositionType::Absolute,
             position: Rect::<Val> {
@@ -202,15 +287,20 @@ fn setup(
             margin: Rect::all(Val::Px(0.)),
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
-            size: Size::new(Val::Px(400.), Val::Px(300.)),
+            // room for the buttons as the menu grows
+            size: Size::new(
+                Val::Px(400.),
+                Val::Px(f32::max(300., 60. + 50. * screen.buttons.len() as f32)),
+            ),
             align_content: AlignContent::Stretch,
             flex_direction: FlexDirection::ColumnReverse,
             ..Default::default()
         };
 
         let button_shift_start = 15.;
-        let button_shift = 45.;
-        let buttons = MENU_BUTTONS
+        let button_shift = f32::min(45., 120. / (screen.buttons.len() as f32 - 1.));
+        let buttons = screen
+            .buttons
             .iter()
             .enumerate()
             .map(|(i, button_item)| {
@@ -352,46 +442,49 @@ fn tear_down(
 
 fn keyboard_input_system(
     mut game_screen: ResMut<crate::GameScreen>,
+    mut match_start: ResMut<crate::game::MatchStart>,
+    (mut config_layers, mut config): (
+        ResMut<crate::difficulty::ConfigLayers>,
+        ResMut<crate::Config>,
+    ),
     mut screen: ResMut<Screen>,
-    keyboard_input: Res<Input<KeyCode>>,
+    (actions, mut settings): (Res<crate::input::Actions>, ResMut<crate::Settings>),
     mut wnds: ResMut<Windows>,
 ) {
     if game_screen.current_screen == CURRENT_SCREEN && screen.loaded {
-        if keyboard_input.just_released(KeyCode::Escape) {
+        if actions.just_released(Action::Back) {
             #[cfg(not(target_arch = "wasm32"))]
             {
                 game_screen.current_screen = crate::Screen::Exit;
             }
-        } else if keyboard_input.just_released(KeyCode::F) {
-            let window = wnds.get_primary_mut().unwrap();
-            match window.mode() {
-                bevy::window::WindowMode::Windowed => {
-                    window.set_mode(bevy::window::WindowMode::BorderlessFullscreen)
-                }
-                _ => window.set_mode(bevy::window::WindowMode::Windowed),
-            }
-        } else if keyboard_input.just_released(KeyCode::Down) {
+        } else if actions.just_released(Action::ToggleFullscreen) {
+            crate::settings::toggle_fullscreen(&mut settings, &mut wnds);
+        } else if actions.just_released(Action::Down) {
             screen.menu_selected = Some(
                 screen
                     .menu_selected
-                    .map(|i| i32::min(MENU_BUTTONS.len() as i32 - 1, i + 1))
+                    .map(|i| i32::min(screen.buttons.len() as i32 - 1, i + 1))
                     .unwrap_or(0),
             );
-        } else if keyboard_input.just_released(KeyCode::Up) {
+        } else if actions.just_released(Action::Up) {
             screen.menu_selected = Some(
                 screen
                     .menu_selected
                     .map(|i| i32::max(0, i - 1))
                     .unwrap_or(0),
             );
-        } else if keyboard_input.just_released(KeyCode::Space)
-            || keyboard_input.just_released(KeyCode::Return)
-        {
-            match screen.menu_selected {
-                Some(0) => game_screen.current_screen = crate::Screen::Game,
-                Some(1) => game_screen.current_screen = crate::Screen::About,
-                Some(2) => game_screen.current_screen = crate::Screen::Exit,
-                _ => (),
+        } else if actions.just_released(Action::Confirm) {
+            if let Some(button) = screen
+                .menu_selected
+                .and_then(|i| screen.buttons.get(i as usize))
+            {
+                select(
+                    *button,
+                    &mut game_screen,
+                    &mut match_start,
+                    (&mut config_layers, &mut config),
+                    &mut settings,
+                );
             }
         }
     }
@@ -399,8 +492,13 @@ fn keyboard_input_system(
 
 fn button_system(
     mut game_screen: ResMut<crate::GameScreen>,
+    mut match_start: ResMut<crate::game::MatchStart>,
+    (mut config_layers, mut config): (
+        ResMut<crate::difficulty::ConfigLayers>,
+        ResMut<crate::Config>,
+    ),
     mut screen: ResMut<Screen>,
-
+    mut settings: ResMut<crate::Settings>,
     mut interaction_query: Query<
         (&Interaction, &crate::ui::button::ButtonId<MenuButton>),
         (With<Button>, Mutated<Interaction>),
@@ -408,18 +506,20 @@ fn button_system(
 ) {
     for (interaction, button_id) in interaction_query.iter_mut() {
         match *interaction {
-            Interaction::Clicked => match button_id.0 {
-                #[cfg(not(target_arch = "wasm32"))]
-                MenuButton::Quit => game_screen.current_screen = crate::Screen::Exit,
-                MenuButton::About => game_screen.current_screen = crate::Screen::About,
-                MenuButton::NewGame => game_screen.current_screen = crate::Screen::Game,
-            },
-            Interaction::Hovered => match button_id.0 {
-                MenuButton::NewGame => screen.menu_selected = Some(0),
-                MenuButton::About => screen.menu_selected = Some(1),
-                #[cfg(not(target_arch = "wasm32"))]
-                MenuButton::Quit => screen.menu_selected = Some(2),
-            },
+            Interaction::Clicked => select(
+                button_id.0,
+                &mut game_screen,
+                &mut match_start,
+                (&mut config_layers, &mut config),
+                &mut settings,
+            ),
+            Interaction::Hovered => {
+                screen.menu_selected = screen
+                    .buttons
+                    .iter()
+                    .position(|button| *button == button_id.0)
+                    .map(|i| i as i32)
+            }
             Interaction::None => screen.menu_selected = None,
         }
     }
@@ -427,6 +527,15 @@ fn button_system(
 
 struct MenuItemSelector(usize);
 
+fn display_difficulty(
+    config_layers: Res<crate::difficulty::ConfigLayers>,
+    mut query: Query<&mut Text, With<DifficultyLabel>>,
+) {
+    for mut text in query.iter_mut() {
+        text.value = format!("Difficulty: {}", config_layers.difficulty);
+    }
+}
+
 fn display_menu_item_selector(
     screen: Res<Screen>,
     mut query: Query<(&MenuItemSelector, &mut Draw)>,
@@ -0,0 +1,98 @@
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use tracing::{info, warn};
+
+use crate::{
+    campaign::Campaign,
+    game::{AsteroidBelt, PlanetFleet},
+    ConfigOverrides,
+};
+
+// how often the files are checked, in seconds
+const CHECK_EVERY: f32 = 1.;
+
+// Watches the files of the `ConfigOverrides` and applies their changes to the running game, so
+// that balancing doesn't need a restart.
+pub struct ConfigWatcher {
+    layers: config::Layers,
+    modified: Vec<Option<SystemTime>>,
+    timer: Timer,
+}
+
+impl ConfigWatcher {
+    pub fn new(layers: config::Layers) -> Self {
+        let modified = modified(&layers);
+        ConfigWatcher {
+            layers,
+            modified,
+            timer: Timer::from_seconds(CHECK_EVERY, true),
+        }
+    }
+}
+
+fn modified(layers: &config::Layers) -> Vec<Option<SystemTime>> {
+    layers
+        .files()
+        .iter()
+        .map(|file| {
+            std::fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+// Applies a changed config to the planet fleets and asteroid belts already spawned.
+pub fn reconfigure(
+    config: &crate::Config,
+    fleets: &mut Query<&mut PlanetFleet>,
+    asteroid_belts: &mut Query<&mut AsteroidBelt>,
+) {
+    for mut fleet in fleets.iter_mut() {
+        fleet.reconfigure(config);
+    }
+    for mut asteroid_belt in asteroid_belts.iter_mut() {
+        asteroid_belt.reconfigure(config);
+    }
+}
+
+pub fn watch_config(
+    (time, clock): (Res<Time>, Res<crate::clock::GameClock>),
+    mut watcher: ResMut<ConfigWatcher>,
+    mut replay: ResMut<crate::replay::ReplayState>,
+    (mut config_layers, mut config, mut campaign): (
+        ResMut<crate::difficulty::ConfigLayers>,
+        ResMut<crate::Config>,
+        ResMut<Campaign>,
+    ),
+    mut fleets: Query<&mut PlanetFleet>,
+    mut asteroid_belts: Query<&mut AsteroidBelt>,
+) {
+    // a replay plays with the config it was recorded with
+    if replay.is_playback() {
+        return;
+    }
+    watcher.timer.tick(time.delta_seconds());
+    if !watcher.timer.just_finished() {
+        return;
+    }
+    let modified = modified(&watcher.layers);
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+
+    match watcher.layers.read::<ConfigOverrides>() {
+        Ok(user) => {
+            info!("config changed, applying it");
+            config_layers.user = user;
+            campaign.reload(config_layers.config(), &mut config);
+            reconfigure(&config, &mut fleets, &mut asteroid_belts);
+            // played back at the same time in the replay of the match
+            replay.record_config(clock.tick(), &config);
+        }
+        // the game goes on with the config it had, the file is read again on its next change
+        Err(err) => warn!("{}, keeping the current config", err),
+    }
+}
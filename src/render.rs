@@ -0,0 +1,290 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::game::{missile::Missile, Asteroid, Moon, OwnedBy, Planet};
+use crate::space::{Shielded, Ship, ShipExploded, SpawnShip};
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(crate::AssetHandles::default())
+            .add_system(spawn_progress)
+            .add_system(shield)
+            .add_system(explosion)
+            .add_system(explode)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, planet_sprite)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, moon_sprite)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, ship_sprite)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, asteroid_sprite)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, missile_sprite);
+    }
+}
+
+pub struct SpawnShipProgress;
+
+fn sprite(
+    material: Handle<ColorMaterial>,
+    transform: &Transform,
+    global_transform: &GlobalTransform,
+) -> SpriteBundle {
+    SpriteBundle {
+        transform: *transform,
+        global_transform: *global_transform,
+        material,
+        ..Default::default()
+    }
+}
+
+fn planet_sprite(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    query: Query<(Entity, &Planet, &Transform, &GlobalTransform), Added<Planet>>,
+) {
+    for (entity, planet, transform, global_transform) in query.iter() {
+        let game_handles = asset_handles.get_game_handles_unsafe();
+        commands.insert(
+            entity,
+            sprite(
+                game_handles.planets[planet.kind].clone(),
+                transform,
+                global_transform,
+            ),
+        );
+    }
+}
+
+fn moon_sprite(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    query: Query<(Entity, &Transform, &GlobalTransform), Added<Moon>>,
+) {
+    for (entity, transform, global_transform) in query.iter() {
+        let game_handles = asset_handles.get_game_handles_unsafe();
+        commands.insert(
+            entity,
+            sprite(
+                game_handles
+                    .orbiters
+                    .choose(&mut rand::thread_rng())
+                    .unwrap()
+                    .clone_weak(),
+                transform,
+                global_transform,
+            ),
+        );
+        let progress = commands
+            .spawn((SpawnShipProgress,))
+            .current_entity()
+            .unwrap();
+        commands.push_children(entity, &[progress]);
+    }
+}
+
+// blue for the player, green then black for rivals, red for the planet
+fn ship_set(owned_by: &OwnedBy) -> usize {
+    match owned_by {
+        OwnedBy::Player(0) => 0,
+        OwnedBy::Player(1) => 1,
+        OwnedBy::Player(_) => 3,
+        OwnedBy::Neutral => 2,
+    }
+}
+
+fn ship_sprite(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    query: Query<(Entity, &Ship, &OwnedBy, &Transform, &GlobalTransform), Added<Ship>>,
+) {
+    for (entity, ship, owned_by, transform, global_transform) in query.iter() {
+        let game_handles = asset_handles.get_game_handles_unsafe();
+        let ship = &game_handles.ships[ship_set(owned_by)][ship.tier];
+        commands.insert(entity, sprite(ship.clone(), transform, global_transform));
+    }
+}
+
+// ten missiles for each ship set, the first of each
+fn missile_sprite(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    query: Query<(Entity, &Missile, &Transform, &GlobalTransform), Added<Missile>>,
+) {
+    for (entity, missile, transform, global_transform) in query.iter() {
+        let game_handles = asset_handles.get_game_handles_unsafe();
+        let sprite_missile = &game_handles.missiles[ship_set(&missile.owner) * 10];
+        commands.insert(
+            entity,
+            sprite(sprite_missile.clone(), transform, global_transform),
+        );
+    }
+}
+
+fn asteroid_sprite(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    query: Query<(Entity, &Transform, &GlobalTransform), Added<Asteroid>>,
+) {
+    for (entity, transform, global_transform) in query.iter() {
+        let game_handles = asset_handles.get_game_handles_unsafe();
+        let meteor = game_handles
+            .meteors
+            .choose(&mut rand::thread_rng())
+            .unwrap();
+        commands.insert(entity, sprite(meteor.clone(), transform, global_transform));
+    }
+}
+
+fn spawn_progress(
+    commands: &mut Commands,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Res<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
+    query: Query<(
+        &SpawnShip,
+        &Children,
+        &OwnedBy,
+        &bevy_rapier2d::physics::RigidBodyHandleComponent,
+    )>,
+    progress_query: Query<Entity, With<SpawnShipProgress>>,
+) {
+    for (spawn, children, owned_by, rigid_body) in query.iter() {
+        if let Some(progress_entity) = children
+            .iter()
+            .find_map(|entity| progress_query.get(*entity).ok())
+        {
+            let color_spawn_progress = match owned_by {
+                OwnedBy::Player(0) => asset_handles.get_color_spawning_self(&mut materials),
+                OwnedBy::Player(_) => asset_handles.get_color_spawning_enemy(&mut materials),
+                OwnedBy::Neutral => asset_handles.get_color_spawning_neutral(&mut materials),
+            };
+            let body = bodies.get(rigid_body.handle()).unwrap();
+
+            let angle = spawn.every.elapsed() / spawn.every.duration() * 2. * std::f32::consts::PI;
+
+            let radius = 300.;
+            let start_x =
+                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).cos() * radius;
+            let start_y =
+                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).sin() * radius;
+            let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+            builder.move_to(bevy_prototype_lyon::prelude::point(start_x, start_y));
+            builder.arc(
+                bevy_prototype_lyon::prelude::point(0., 0.),
+                radius,
+                radius,
+                angle,
+                0.,
+            );
+            let path = builder.build();
+            let sprite = path.stroke(
+                color_spawn_progress.clone(),
+                &mut meshes,
+                Vec3::new(0.0, 0.0, 0.0),
+                &bevy_prototype_lyon::prelude::StrokeOptions::default()
+                    .with_line_width(20.)
+                    .with_line_cap(bevy_prototype_lyon::prelude::LineCap::Round)
+                    .with_line_join(bevy_prototype_lyon::prelude::LineJoin::Round),
+            );
+
+            commands.insert(progress_entity, sprite);
+        }
+    }
+}
+
+fn shield(
+    commands: &mut Commands,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Res<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
+    shieldeds: Query<(Entity, &Parent, &Shielded)>,
+    query: Query<(
+        &Planet,
+        Option<&bevy_rapier2d::physics::RigidBodyHandleComponent>,
+    )>,
+) {
+    for (shield_entity, planet_entity, shielded) in shieldeds.iter() {
+        let (planet, rigid_body) = query.get(planet_entity.0).unwrap();
+
+        if let Some(rigid_body) = rigid_body {
+            let color_spawn_progress = asset_handles.get_color_spawning_enemy(&mut materials);
+            let body = bodies.get(rigid_body.handle()).unwrap();
+
+            let angle = (shielded.timer.duration() - shielded.timer.elapsed())
+                / shielded.timer.duration()
+                * 2.
+                * std::f32::consts::PI;
+
+            let radius = (planet.radius() + 5.) * 10. - 20.;
+            let start_x =
+                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).cos() * radius;
+            let start_y =
+                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).sin() * radius;
+            let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
+            builder.move_to(bevy_prototype_lyon::prelude::point(start_x, start_y));
+            builder.arc(
+                bevy_prototype_lyon::prelude::point(0., 0.),
+                radius,
+                radius,
+                angle,
+                0.,
+            );
+            let path = builder.build();
+            let sprite = path.stroke(
+                color_spawn_progress.clone(),
+                &mut meshes,
+                Vec3::new(0.0, 0.0, 0.0),
+                &bevy_prototype_lyon::prelude::StrokeOptions::default()
+                    .with_line_width(20.)
+                    .with_line_cap(bevy_prototype_lyon::prelude::LineCap::Round)
+                    .with_line_join(bevy_prototype_lyon::prelude::LineJoin::Round),
+            );
+
+            commands.insert(shield_entity, sprite);
+        }
+    }
+}
+
+fn explosion(
+    commands: &mut Commands,
+    asset_handles: Res<crate::AssetHandles>,
+    (mut event_reader, events): (Local<EventReader<ShipExploded>>, Res<Events<ShipExploded>>),
+) {
+    for ShipExploded(gt) in event_reader.iter(&events) {
+        let explosion_handle = asset_handles.get_game_handles_unsafe().explosion_handle;
+        commands
+            .spawn(SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    ..Default::default()
+                },
+                texture_atlas: explosion_handle.clone(),
+                transform: (*gt).into(),
+                ..Default::default()
+            })
+            .with(Explosion {
+                timer: Timer::from_seconds(0.1, true),
+            });
+    }
+}
+
+struct Explosion {
+    timer: Timer,
+}
+fn explode(
+    commands: &mut Commands,
+    time: Res<Time>,
+    mut query: Query<(&mut Explosion, &mut TextureAtlasSprite, Entity)>,
+) {
+    for (mut explosion, mut atlas_sprite, entity) in query.iter_mut() {
+        explosion.timer.tick(time.delta_seconds());
+        if explosion.timer.just_finished() {
+            match atlas_sprite.index {
+                6 => {
+                    commands.despawn_recursive(entity);
+                }
+                n => atlas_sprite.index = n + 1,
+            }
+        }
+    }
+}
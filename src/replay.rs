@@ -0,0 +1,283 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::game::{Moon, Order, OwnedBy, Planet, Ratio, Target};
+use crate::space::SpawnShipType;
+
+pub const LAST_REPLAY: &str = "last.replay";
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ReplayState>()
+            .add_system(playback)
+            .add_system(end_playback)
+            .add_system_to_stage(bevy::app::stage::POST_UPDATE, record);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum RecordedAction {
+    SendShips {
+        from: i32,
+        towards: Target,
+        ratio: Ratio,
+    },
+    SwitchShipClass {
+        moon: i32,
+        class: SpawnShipType,
+    },
+    // the config files changed during the match
+    Reconfigure(Box<crate::Config>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedOrder {
+    pub tick: u64,
+    pub action: RecordedAction,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: crate::Config,
+    pub map: crate::game::map::MapDefinition,
+    pub deltas: Vec<f32>,
+    pub orders: Vec<RecordedOrder>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+// What the game was started with, put back once the replay has been watched.
+pub struct BeforeReplay {
+    pub fixed_seed: Option<u64>,
+    pub config: crate::Config,
+    pub map: crate::game::map::MapDefinition,
+}
+
+#[derive(Default)]
+pub struct ReplayState {
+    replay: Replay,
+    playback: bool,
+    recording: bool,
+    next_order: usize,
+    before: Option<BeforeReplay>,
+}
+
+impl ReplayState {
+    pub fn playback(replay: Replay, before: BeforeReplay) -> Self {
+        ReplayState {
+            replay,
+            playback: true,
+            before: Some(before),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_playback(&self) -> bool {
+        self.playback
+    }
+
+    // what has been recorded of the current match, or is being played back
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn record_config(&mut self, tick: u64, config: &crate::Config) {
+        if self.recording {
+            self.replay.orders.push(RecordedOrder {
+                tick,
+                action: RecordedAction::Reconfigure(Box::new(config.clone())),
+            });
+        }
+    }
+}
+
+fn record(
+    game_screen: Res<crate::GameScreen>,
+    (clock, rng, config, map): (
+        Res<crate::clock::GameClock>,
+        Res<crate::rng::MatchRng>,
+        Res<crate::Config>,
+        Res<crate::game::map::MapDefinition>,
+    ),
+    (mut state, match_start): (ResMut<ReplayState>, Res<crate::game::MatchStart>),
+    (mut event_reader, events): (Local<EventReader<Order>>, Res<Events<Order>>),
+    moons: Query<&Moon>,
+) {
+    // the match goes on after the settings opened from the pause menu
+    if *match_start == crate::game::MatchStart::Suspended {
+        return;
+    }
+    let in_game = game_screen.current_screen == crate::Screen::Game;
+
+    // the clock is only back to 0 on the frame a match is set up
+    if in_game && clock.tick() == 0 {
+        state.next_order = 0;
+        if !state.playback {
+            state.replay = Replay {
+                seed: rng.seed(),
+                config: config.clone(),
+                map: map.clone(),
+                ..Default::default()
+            };
+            state.recording = true;
+        }
+    }
+    if !state.recording {
+        return;
+    }
+
+    if !in_game {
+        state.recording = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        match state.replay.save(LAST_REPLAY) {
+            Ok(()) => info!("replay saved to {}", LAST_REPLAY),
+            Err(err) => warn!("could not save replay: {}", err),
+        }
+        return;
+    }
+
+    if clock.tick() as usize == state.replay.deltas.len() + 1 {
+        state.replay.deltas.push(clock.delta_seconds());
+    }
+    for order in event_reader.iter(&events) {
+        let action = match order {
+            Order::SendShips {
+                owner: OwnedBy::Player(0),
+                from,
+                towards,
+                ratio,
+            } => match moons.get(*from) {
+                Ok(from) => RecordedAction::SendShips {
+                    from: from.index,
+                    towards: moons
+                        .get(*towards)
+                        .map(|moon| Target::Moon(moon.index))
+                        .unwrap_or(Target::Planet),
+                    ratio: *ratio,
+                },
+                Err(_) => continue,
+            },
+            Order::SwitchShipClass {
+                owner: OwnedBy::Player(0),
+                moon,
+                class,
+            } => match moons.get(*moon) {
+                Ok(moon) => RecordedAction::SwitchShipClass {
+                    moon: moon.index,
+                    class: *class,
+                },
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        state.replay.orders.push(RecordedOrder {
+            tick: clock.tick(),
+            action,
+        });
+    }
+}
+
+fn playback(
+    game_screen: Res<crate::GameScreen>,
+    clock: Res<crate::clock::GameClock>,
+    mut state: ResMut<ReplayState>,
+    (mut orders, mut config): (ResMut<Events<Order>>, ResMut<crate::Config>),
+    moons: Query<(Entity, &Moon)>,
+    planets: Query<Entity, With<Planet>>,
+    (mut fleets, mut asteroid_belts): (
+        Query<&mut crate::game::PlanetFleet>,
+        Query<&mut crate::game::AsteroidBelt>,
+    ),
+) {
+    if !state.playback || game_screen.current_screen != crate::Screen::Game {
+        return;
+    }
+    let moon_entity = |index: i32| {
+        moons
+            .iter()
+            .find(|(_, moon)| moon.index == index)
+            .map(|(entity, _)| entity)
+    };
+    while let Some(order) = state.replay.orders.get(state.next_order).cloned() {
+        if order.tick > clock.tick() {
+            break;
+        }
+        state.next_order += 1;
+        match order.action {
+            RecordedAction::SendShips {
+                from,
+                towards,
+                ratio,
+            } => {
+                let towards = match towards {
+                    Target::Planet => planets.iter().next(),
+                    Target::Moon(index) => moon_entity(index),
+                };
+                if let (Some(from), Some(towards)) = (moon_entity(from), towards) {
+                    orders.send(Order::SendShips {
+                        owner: OwnedBy::Player(0),
+                        from,
+                        towards,
+                        ratio,
+                    });
+                }
+            }
+            RecordedAction::SwitchShipClass { moon, class } => {
+                if let Some(moon) = moon_entity(moon) {
+                    orders.send(Order::SwitchShipClass {
+                        owner: OwnedBy::Player(0),
+                        moon,
+                        class,
+                    });
+                }
+            }
+            RecordedAction::Reconfigure(recorded) => {
+                *config = *recorded;
+                crate::reload::reconfigure(&config, &mut fleets, &mut asteroid_belts);
+            }
+        }
+    }
+}
+
+// The replayed match is over once it has left the game and its end screen, the next matches are
+// played normally.
+fn end_playback(
+    game_screen: Res<crate::GameScreen>,
+    mut state: ResMut<ReplayState>,
+    (mut clock, mut rng, mut config, mut map): (
+        ResMut<crate::clock::GameClock>,
+        ResMut<crate::rng::MatchRng>,
+        ResMut<crate::Config>,
+        ResMut<crate::game::map::MapDefinition>,
+    ),
+) {
+    if !state.playback
+        || game_screen.current_screen == crate::Screen::Game
+        || game_screen.current_screen == crate::Screen::End
+    {
+        return;
+    }
+    info!("replay over");
+    state.playback = false;
+    state.next_order = 0;
+    clock.clear_script();
+    if let Some(before) = state.before.take() {
+        rng.fix_seed(before.fixed_seed);
+        *config = before.config;
+        *map = before.map;
+    }
+}
@@ -0,0 +1,71 @@
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+pub struct MatchRng {
+    fixed_seed: Option<u64>,
+    seed: u64,
+    rng: ChaCha20Rng,
+}
+
+impl MatchRng {
+    pub fn with_seed(fixed_seed: Option<u64>) -> Self {
+        let mut rng = MatchRng {
+            fixed_seed,
+            seed: 0,
+            rng: ChaCha20Rng::seed_from_u64(0),
+        };
+        rng.new_match();
+        rng
+    }
+
+    // reseed for a new match, with the seed from startup if there is one
+    pub fn new_match(&mut self) {
+        self.seed = self.fixed_seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.rng = ChaCha20Rng::seed_from_u64(self.seed);
+    }
+
+    // seed used by the next matches, `None` for a random one each time
+    pub fn fix_seed(&mut self, fixed_seed: Option<u64>) {
+        self.fixed_seed = fixed_seed;
+    }
+
+    // continue a saved match where its numbers were, as if it had not been interrupted
+    pub fn resume(&mut self, seed: u64, position: u64) {
+        self.seed = seed;
+        self.rng = ChaCha20Rng::seed_from_u64(seed);
+        self.rng.set_word_pos(position as u128);
+    }
+
+    // how many numbers the match has drawn since its seed, to save it
+    pub fn position(&self) -> u64 {
+        self.rng.get_word_pos() as u64
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for MatchRng {
+    fn default() -> Self {
+        MatchRng::with_seed(None)
+    }
+}
+
+impl RngCore for MatchRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,422 @@
+use bevy::prelude::*;
+use tracing::{info, warn};
+
+use crate::input::Action;
+
+const CURRENT_SCREEN: crate::Screen = crate::Screen::Settings;
+
+pub const RESOLUTIONS: &[(u32, u32)] = &[(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const VOLUME_STEP: f32 = 0.1;
+
+struct ScreenTag;
+
+struct Screen {
+    loaded: bool,
+    selected: Option<usize>,
+}
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            loaded: false,
+            selected: None,
+        }
+    }
+}
+
+pub struct Plugin;
+impl bevy::app::Plugin for Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Screen::default())
+            .add_system(setup)
+            .add_system(keyboard_input_system)
+            .add_system(button_system)
+            .add_system(display_settings)
+            .add_system_to_stage(crate::custom_stage::TEAR_DOWN, tear_down);
+    }
+}
+
+pub fn save(settings: &crate::Settings) {
+    if let Err(err) = settings.save() {
+        warn!("could not save settings: {}", err);
+    }
+}
+
+pub fn apply(settings: &crate::Settings, windows: &mut Windows) {
+    let window = windows.get_primary_mut().unwrap();
+    window.set_resolution(settings.width, settings.height);
+    window.set_vsync(settings.vsync);
+    window.set_mode(settings.window_mode.into());
+}
+
+// F switches between a window and a borderless fullscreen, and is remembered for the next start
+pub fn toggle_fullscreen(settings: &mut crate::Settings, windows: &mut Windows) {
+    settings.window_mode = match settings.window_mode {
+        crate::WindowMode::Windowed => crate::WindowMode::Borderless,
+        _ => crate::WindowMode::Windowed,
+    };
+    apply(settings, windows);
+    save(settings);
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsButton {
+    Resolution,
+    WindowMode,
+    VSync,
+    Volume,
+    Controls,
+    Back,
+}
+
+impl Into<String> for SettingsButton {
+    fn into(self) -> String {
+        match self {
+            SettingsButton::Resolution => "Resolution".to_string(),
+            SettingsButton::WindowMode => "Window".to_string(),
+            SettingsButton::VSync => "VSync".to_string(),
+            SettingsButton::Volume => "Volume".to_string(),
+            SettingsButton::Controls => "Controls".to_string(),
+            SettingsButton::Back => "Back".to_string(),
+        }
+    }
+}
+
+const SETTINGS_BUTTONS: &[SettingsButton] = &[
+    SettingsButton::Resolution,
+    SettingsButton::WindowMode,
+    SettingsButton::VSync,
+    SettingsButton::Volume,
+    SettingsButton::Controls,
+    SettingsButton::Back,
+];
+
+struct ValueLabel(usize);
+
+fn setup(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    mut asset_handles: ResMut<crate::AssetHandles>,
+    asset_server: Res<AssetServer>,
+    mut nine_patches: ResMut<Assets<bevy_ninepatch::NinePatchBuilder<()>>>,
+    mut buttons: ResMut<Assets<crate::ui::button::Button>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    match_start: Res<crate::game::MatchStart>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && !screen.loaded {
+        info!("Loading screen");
+
+        if *match_start == crate::game::MatchStart::Suspended {
+            let backdrop = crate::game::pause::spawn_backdrop(commands, &mut materials);
+            commands.insert_one(backdrop, ScreenTag);
+        }
+
+        let button_handle = asset_handles.get_button_handle(
+            &asset_server,
+            &mut materials,
+            &mut nine_patches,
+            &mut buttons,
+        );
+        let button = buttons.get(&button_handle).unwrap();
+
+        let font: Handle<Font> = asset_handles.get_font_main_handle(&asset_server);
+        let font_sub: Handle<Font> = asset_handles.get_font_sub_handle(&asset_server);
+
+        let color_none = materials.add(Color::NONE.into());
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(10.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(5.),
+                    },
+                    size: Size::<Val> {
+                        height: Val::Px(75.),
+                        width: Val::Auto,
+                    },
+                    ..Default::default()
+                },
+                material: color_none.clone(),
+                ..Default::default()
+            })
+            .with_children(|title_parent| {
+                title_parent.spawn(TextBundle {
+                    style: Style {
+                        size: Size {
+                            height: Val::Px(75.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text {
+                        value: "Settings".to_string(),
+                        font: font.clone(),
+                        style: TextStyle {
+                            color: crate::ui::ColorScheme::TEXT,
+                            font_size: 75.,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                });
+            })
+            .with(ScreenTag);
+
+        let rows = SETTINGS_BUTTONS
+            .iter()
+            .enumerate()
+            .map(|(i, settings_button)| {
+                let row = commands
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            margin: Rect {
+                                bottom: Val::Px(10.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        material: color_none.clone(),
+                        ..Default::default()
+                    })
+                    .current_entity()
+                    .unwrap();
+                let label = button.add(
+                    commands,
+                    200.,
+                    40.,
+                    Rect::all(Val::Auto),
+                    font.clone(),
+                    *settings_button,
+                    20.,
+                );
+                commands
+                    .spawn(TextBundle {
+                        style: Style {
+                            size: Size {
+                                height: Val::Px(20.),
+                                ..Default::default()
+                            },
+                            margin: Rect {
+                                left: Val::Px(20.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: "".to_string(),
+                            font: font_sub.clone(),
+                            style: TextStyle {
+                                color: crate::ui::ColorScheme::TEXT,
+                                font_size: 20.,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    })
+                    .with(ValueLabel(i));
+                let value = commands.current_entity().unwrap();
+                commands.push_children(row, &[label, value]);
+                row
+            })
+            .collect::<Vec<_>>();
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect::<Val> {
+                        left: Val::Percent(10.),
+                        right: Val::Undefined,
+                        bottom: Val::Undefined,
+                        top: Val::Percent(20.),
+                    },
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..Default::default()
+                },
+                material: color_none,
+                ..Default::default()
+            })
+            .with(ScreenTag);
+        let list = commands.current_entity().unwrap();
+        commands.push_children(list, rows.as_slice());
+
+        screen.selected = None;
+        screen.loaded = true;
+    }
+}
+
+fn tear_down(
+    commands: &mut Commands,
+    game_screen: Res<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    query: Query<Entity, With<ScreenTag>>,
+) {
+    if game_screen.current_screen != CURRENT_SCREEN && screen.loaded {
+        info!("tear down");
+
+        for entity in query.iter() {
+            commands.despawn_recursive(entity);
+        }
+
+        screen.loaded = false;
+    }
+}
+
+// `forward` is false to go back through the values of a setting
+fn select(
+    button: SettingsButton,
+    forward: bool,
+    game_screen: &mut crate::GameScreen,
+    match_start: &crate::game::MatchStart,
+    settings: &mut crate::Settings,
+    windows: &mut Windows,
+) {
+    let step = |i: usize, len: usize| {
+        if forward {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        }
+    };
+    match button {
+        SettingsButton::Resolution => {
+            let current = RESOLUTIONS
+                .iter()
+                .position(|resolution| *resolution == (settings.width, settings.height))
+                .unwrap_or(0);
+            let (width, height) = RESOLUTIONS[step(current, RESOLUTIONS.len())];
+            settings.width = width;
+            settings.height = height;
+        }
+        SettingsButton::WindowMode => {
+            let current = crate::WindowMode::ALL
+                .iter()
+                .position(|mode| *mode == settings.window_mode)
+                .unwrap_or(0);
+            settings.window_mode =
+                crate::WindowMode::ALL[step(current, crate::WindowMode::ALL.len())];
+        }
+        SettingsButton::VSync => settings.vsync = !settings.vsync,
+        SettingsButton::Volume => {
+            let steps = (1. / VOLUME_STEP).round() as usize + 1;
+            let current = (settings.volume / VOLUME_STEP).round() as usize;
+            settings.volume = step(current, steps) as f32 * VOLUME_STEP;
+        }
+        SettingsButton::Controls => {
+            game_screen.current_screen = crate::Screen::Controls;
+            return;
+        }
+        SettingsButton::Back => {
+            // back to the paused match when coming from it, the menu otherwise
+            game_screen.current_screen = if *match_start == crate::game::MatchStart::Suspended {
+                crate::Screen::Game
+            } else {
+                crate::Screen::Menu
+            };
+            return;
+        }
+    }
+    apply(settings, windows);
+    save(settings);
+}
+
+fn keyboard_input_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    actions: Res<crate::input::Actions>,
+    (mut settings, match_start): (ResMut<crate::Settings>, Res<crate::game::MatchStart>),
+    mut wnds: ResMut<Windows>,
+) {
+    if game_screen.current_screen == CURRENT_SCREEN && screen.loaded {
+        let forward =
+            actions.just_released(Action::Confirm) || actions.just_released(Action::Right);
+        if actions.just_released(Action::Back) {
+            select(
+                SettingsButton::Back,
+                true,
+                &mut game_screen,
+                &match_start,
+                &mut settings,
+                &mut wnds,
+            );
+        } else if actions.just_released(Action::Down) {
+            screen.selected = Some(
+                screen
+                    .selected
+                    .map(|i| usize::min(SETTINGS_BUTTONS.len() - 1, i + 1))
+                    .unwrap_or(0),
+            );
+        } else if actions.just_released(Action::Up) {
+            screen.selected = Some(screen.selected.map(|i| i.saturating_sub(1)).unwrap_or(0));
+        } else if forward || actions.just_released(Action::Left) {
+            if let Some(button) = screen.selected.and_then(|i| SETTINGS_BUTTONS.get(i)) {
+                select(
+                    *button,
+                    forward,
+                    &mut game_screen,
+                    &match_start,
+                    &mut settings,
+                    &mut wnds,
+                );
+            }
+        }
+    }
+}
+
+fn button_system(
+    mut game_screen: ResMut<crate::GameScreen>,
+    mut screen: ResMut<Screen>,
+    (mut settings, match_start): (ResMut<crate::Settings>, Res<crate::game::MatchStart>),
+    mut wnds: ResMut<Windows>,
+    mut interaction_query: Query<
+        (&Interaction, &crate::ui::button::ButtonId<SettingsButton>),
+        (With<Button>, Mutated<Interaction>),
+    >,
+) {
+    for (interaction, button_id) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => select(
+                button_id.0,
+                true,
+                &mut game_screen,
+                &match_start,
+                &mut settings,
+                &mut wnds,
+            ),
+            Interaction::Hovered => {
+                screen.selected = SETTINGS_BUTTONS
+                    .iter()
+                    .position(|button| *button == button_id.0)
+            }
+            Interaction::None => screen.selected = None,
+        }
+    }
+}
+
+fn display_settings(
+    screen: Res<Screen>,
+    settings: Res<crate::Settings>,
+    mut query: Query<(&ValueLabel, &mut Text)>,
+) {
+    for (label, mut text) in query.iter_mut() {
+        text.value = match SETTINGS_BUTTONS[label.0] {
+            SettingsButton::Resolution => format!("{}x{}", settings.width, settings.height),
+            SettingsButton::WindowMode => settings.window_mode.to_string(),
+            SettingsButton::VSync => if settings.vsync { "on" } else { "off" }.to_string(),
+            SettingsButton::Volume => format!("{}%", (settings.volume * 100.).round()),
+            SettingsButton::Controls => "keyboard, mouse and gamepad".to_string(),
+            SettingsButton::Back => "".to_string(),
+        };
+        text.style.color = if screen.selected == Some(label.0) {
+            crate::ui::ColorScheme::TEXT_HIGHLIGHT
+        } else {
+            crate::ui::ColorScheme::TEXT
+        };
+    }
+}
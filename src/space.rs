@@ -1,11 +1,14 @@
 use bevy::prelude::*;
-use rand::{seq::SliceRandom, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 pub struct Ship {
     pub hit_points: i32,
+    pub speed: f32,
+    pub tier: usize,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum RotationDirection {
     Clockwise,
     CounterClockwise,
@@ -26,7 +29,7 @@ impl RotationDirection {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum Rotation {
     Free,
     Fixed,
@@ -43,12 +46,21 @@ pub struct Orbiter {
 }
 
 const SHIP_ORBITING_DISTANCE: f32 = 50.;
+// speeds of ships and missiles are made for frames of 1/60s, physics applies them over the
+// match time
+pub const SPEED_FRAME: f32 = 1. / 60.;
 
 impl Orbiter {
-    pub fn every(speed: f32, around: Entity, direction: RotationDirection, distance: f32) -> Self {
+    pub fn every(
+        speed: f32,
+        around: Entity,
+        direction: RotationDirection,
+        distance: f32,
+        rng: &mut impl Rng,
+    ) -> Self {
         Self {
             speed,
-            offset: rand::thread_rng().gen_range(0., 2. * std::f32::consts::PI),
+            offset: rng.gen_range(0., 2. * std::f32::consts::PI),
             direction,
             distance,
             around,
@@ -86,42 +98,90 @@ pub struct MoveTowards {
     pub from: Entity,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum SpawnShipType {
     Neutral,
     Basic,
-    // Small,
-    // Large,
+    // many weak and fast ships
+    Small,
+    // a few slow ships, hard to take down
+    Large,
+}
+
+impl std::fmt::Display for SpawnShipType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnShipType::Neutral => write!(f, "Neutral"),
+            SpawnShipType::Basic => write!(f, "Basic"),
+            SpawnShipType::Small => write!(f, "Small"),
+            SpawnShipType::Large => write!(f, "Large"),
+        }
+    }
 }
 
 impl SpawnShipType {
-    pub fn to_components(&self, rotation_direction: RotationDirection) -> SpawnShip {
+    pub fn delay(&self) -> f32 {
         let base_delay = 5.;
+        match self {
+            SpawnShipType::Neutral => base_delay * 1.2,
+            SpawnShipType::Basic => base_delay,
+            SpawnShipType::Small => base_delay / 2.,
+            SpawnShipType::Large => base_delay * 2.,
+        }
+    }
+
+    pub fn hit_points(&self) -> i32 {
         let base_hit_points = 2;
         match self {
-            SpawnShipType::Neutral => SpawnShip {
-                every: Timer::from_seconds(base_delay * 1.2, true),
-                scale: 1.,
-                rotation_direction,
-                hit_points: base_hit_points,
-            },
-            SpawnShipType::Basic => SpawnShip {
-                every: Timer::from_seconds(base_delay, true),
-                scale: 1.,
-                rotation_direction,
-                hit_points: base_hit_points + 1,
-            },
-            // SpawnShipType::Small => SpawnShip {
-            //     every: Timer::from_seconds(base_delay / 2., true),
-            //     scale: 1.,
-            //     rotation_direction,
-            //     hit_points: base_hit_points / 2,
-            // },
-            // SpawnShipType::Large => SpawnShip {
-            //     every: Timer::from_seconds(base_delay * 2., true),
-            //     scale: 1.,
-            //     rotation_direction,
-            //     hit_points: base_hit_points * 2,
-            // },
+            SpawnShipType::Neutral => base_hit_points,
+            SpawnShipType::Basic => base_hit_points + 1,
+            SpawnShipType::Small => base_hit_points / 2,
+            SpawnShipType::Large => base_hit_points * 3,
+        }
+    }
+
+    // factor of the speed of ships sent to another moon
+    pub fn speed(&self) -> f32 {
+        match self {
+            SpawnShipType::Neutral | SpawnShipType::Basic => 1.,
+            SpawnShipType::Small => 1.4,
+            SpawnShipType::Large => 0.7,
+        }
+    }
+
+    // factor of the size of the ship and of its collider
+    pub fn size(&self) -> f32 {
+        match self {
+            SpawnShipType::Neutral | SpawnShipType::Basic => 1.,
+            SpawnShipType::Small => 0.7,
+            SpawnShipType::Large => 1.5,
+        }
+    }
+
+    // sprite used in the ship set of the owner, from 0 to 4
+    pub fn tier(&self) -> usize {
+        match self {
+            SpawnShipType::Small => 0,
+            SpawnShipType::Neutral => 1,
+            SpawnShipType::Basic => 2,
+            SpawnShipType::Large => 4,
+        }
+    }
+
+    // classes a player can switch their moons to, in order
+    pub fn next(&self) -> Self {
+        match self {
+            SpawnShipType::Neutral | SpawnShipType::Large => SpawnShipType::Basic,
+            SpawnShipType::Basic => SpawnShipType::Small,
+            SpawnShipType::Small => SpawnShipType::Large,
+        }
+    }
+
+    pub fn to_components(&self, rotation_direction: RotationDirection) -> SpawnShip {
+        SpawnShip {
+            hit_points: self.hit_points(),
+            kind: *self,
+            ..SpawnShip::every(self.delay(), rotation_direction)
         }
     }
 }
@@ -132,6 +192,7 @@ pub struct SpawnShip {
     pub scale: f32,
     pub rotation_direction: RotationDirection,
     pub hit_points: i32,
+    pub kind: SpawnShipType,
 }
 
 impl SpawnShip {
@@ -141,20 +202,32 @@ impl SpawnShip {
             scale: 1.,
             rotation_direction,
             hit_points: 1,
+            kind: SpawnShipType::Neutral,
         }
     }
 
+    // orbit and ships of a bigger body, like the planet of the menu
     pub fn with_scale(mut self, scale: f32) -> Self {
         self.scale = scale;
 
         self
     }
 
-    pub fn with_headstart(mut self) -> Self {
-        self.every.set_elapsed(3. * self.every.duration() / 4.);
+    // `progress` of the way to the first ship already done, between 0 and 1
+    pub fn with_headstart(mut self, progress: f32) -> Self {
+        self.every.set_elapsed(progress * self.every.duration());
 
         self
     }
+
+    // start producing another class, keeping the progress towards the next ship
+    pub fn switch_to(&mut self, kind: SpawnShipType) {
+        let progress = self.every.elapsed() / self.every.duration();
+        self.every = Timer::from_seconds(kind.delay(), true);
+        self.every.set_elapsed(progress * kind.delay());
+        self.hit_points = kind.hit_points();
+        self.kind = kind;
+    }
 }
 
 pub struct Plugin;
@@ -166,119 +239,55 @@ impl bevy::app::Plugin for Plugin {
             .add_system(ship_collision)
             .add_system(object_collision)
             .add_system(game_events)
-            .add_system(explode)
             .add_system(shielded)
-            .add_system(manage_shield);
+            .add_system(manage_shield)
+            .add_event::<ShipExploded>();
     }
 }
-pub struct SpawnShipProgress;
+
+pub struct ShipExploded(pub GlobalTransform);
 
 fn spawn_ship(
     commands: &mut Commands,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
+    mut rng: ResMut<crate::rng::MatchRng>,
     config: Res<crate::Config>,
-    mut asset_handles: ResMut<crate::AssetHandles>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    bodies: Res<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
+    mut interesting_events: ResMut<Events<crate::game::InterestingEvent>>,
     mut query: Query<(
         &mut SpawnShip,
         &GlobalTransform,
         Entity,
-        &Children,
         &crate::game::OwnedBy,
-        Option<&bevy_rapier2d::physics::RigidBodyHandleComponent>,
     )>,
-    progress_query: Query<Entity, With<SpawnShipProgress>>,
 ) {
-    for (mut spawn, global_transform, entity, children, owned_by, rigid_body) in query.iter_mut() {
-        let game_handles = asset_handles.get_game_handles_unsafe();
-        spawn.every.tick(time.delta_seconds());
-
-        if let Some(progress_entity) = children
-            .iter()
-            .find_map(|entity| progress_query.get(*entity).ok())
-        {
-            if let Some(rigid_body) = rigid_body {
-                let color_spawn_progress = match owned_by {
-                    crate::game::OwnedBy::Player(0) => {
-                        asset_handles.get_color_spawning_self(&mut materials)
-                    }
-                    crate::game::OwnedBy::Player(_) => {
-                        asset_handles.get_color_spawning_enemy(&mut materials)
-                    }
-                    crate::game::OwnedBy::Neutral => {
-                        asset_handles.get_color_spawning_neutral(&mut materials)
-                    }
-                };
-                let body = bodies.get(rigid_body.handle()).unwrap();
-
-                let angle =
-                    spawn.every.elapsed() / spawn.every.duration() * 2. * std::f32::consts::PI;
-
-                let radius = 300.;
-                let start_x =
-                    (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).cos() * radius;
-                let start_y =
-                    (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).sin() * radius;
-                let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
-                builder.move_to(bevy_prototype_lyon::prelude::point(start_x, start_y));
-                builder.arc(
-                    bevy_prototype_lyon::prelude::point(0., 0.),
-                    radius,
-                    radius,
-                    angle,
-                    0.,
-                );
-                let path = builder.build();
-                let sprite = path.stroke(
-                    color_spawn_progress.clone(),
-                    &mut meshes,
-                    Vec3::new(0.0, 0.0, 0.0),
-                    &bevy_prototype_lyon::prelude::StrokeOptions::default()
-                        .with_line_width(20.)
-                        .with_line_cap(bevy_prototype_lyon::prelude::LineCap::Round)
-                        .with_line_join(bevy_prototype_lyon::prelude::LineJoin::Round),
-                );
-
-                commands.insert(progress_entity, sprite);
-            }
-        }
+    for (mut spawn, global_transform, entity, owned_by) in query.iter_mut() {
+        spawn.every.tick(clock.delta_seconds());
 
         if spawn.every.just_finished() {
-            let ship = game_handles.ships[match owned_by {
-                crate::game::OwnedBy::Player(i) => *i,
-                crate::game::OwnedBy::Neutral => 2,
-            }]
-            .choose(&mut rand::thread_rng())
-            .unwrap();
             let orbiter = Orbiter::every(
-                rand::thread_rng().gen_range(0.5, 1.),
+                rng.gen_range(0.5, 1.),
                 entity,
                 spawn.rotation_direction,
                 spawn.scale * SHIP_ORBITING_DISTANCE,
+                &mut *rng,
             );
 
             let mut translation = global_transform.translation.clone();
             translation.z = crate::Z_SHIP;
 
-            let lucky_draw = match (
-                owned_by,
-                rand::thread_rng().gen_bool(config.bigger_player_ship_rate),
-            ) {
+            let lucky_draw = match (owned_by, rng.gen_bool(config.bigger_player_ship_rate)) {
                 (crate::game::OwnedBy::Player(0), true) => config.bigger_player_ship_change,
                 _ => 1.,
             };
 
-            commands.spawn(SpriteBundle {
-                transform: Transform {
+            commands.spawn((
+                Transform {
                     translation,
-                    scale: Vec3::splat(spawn.scale * 0.15 * (lucky_draw)),
+                    scale: Vec3::splat(spawn.scale * 0.15 * spawn.kind.size() * lucky_draw),
                     ..Default::default()
                 },
-                material: ship.clone(),
-                ..Default::default()
-            });
+                GlobalTransform::default(),
+            ));
             let entity = commands.current_entity().unwrap();
             commands
                 .with(
@@ -290,11 +299,14 @@ fn spawn_ship(
                         .user_data(entity.to_bits() as u128),
                 )
                 .with(bevy_rapier2d::rapier::geometry::ColliderBuilder::ball(
-                    spawn.scale * 5. * (lucky_draw),
+                    spawn.scale * 5. * spawn.kind.size() * lucky_draw,
                 ));
             commands.with(orbiter).with(owned_by.clone()).with(Ship {
                 hit_points: (spawn.hit_points as f32 * lucky_draw).ceil() as i32,
+                speed: spawn.kind.speed(),
+                tier: spawn.kind.tier(),
             });
+            interesting_events.send(crate::game::InterestingEvent::ShipBuilt(owned_by.clone()));
         }
     }
 }
@@ -314,7 +326,7 @@ pub fn target_orbiting_position(
 }
 
 fn orbite_around(
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
     mut bodies: ResMut<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
     orbiters: Query<(&bevy_rapier2d::physics::RigidBodyHandleComponent, &Orbiter)>,
     centers: Query<&GlobalTransform>,
@@ -330,7 +342,7 @@ fn orbite_around(
                     center_transform.translation.x,
                     center_transform.translation.y,
                 ),
-            target_orbiting_position(time.seconds_since_startup() as f32, orbiter),
+            target_orbiting_position(clock.seconds_since_start(), orbiter),
         );
         body.linvel = linvel * orbiter.speed * orbiter.distance;
         match orbiter.rotation {
@@ -362,7 +374,7 @@ pub fn go_from_to_rapier(from: Vector<f32>, to: Vector<f32>) -> (Vector<f32>, f3
 
 fn move_towards(
     commands: &mut Commands,
-    time: Res<Time>,
+    mut rng: ResMut<crate::rng::MatchRng>,
     mut bodies: ResMut<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
     movers: Query<(
         Entity,
@@ -395,7 +407,7 @@ fn move_towards(
                 commands.insert_one(
                     moving,
                     Orbiter::every_with_offset(
-                        rand::thread_rng().gen_range(0.8, 1.2),
+                        rng.gen_range(0.8, 1.2),
                         towards.towards,
                         if owned_by == moon_owned_by {
                             spawn.rotation_direction
@@ -409,7 +421,7 @@ fn move_towards(
             }
         }
 
-        body.linvel = linvel * towards.speed * time.delta_seconds();
+        body.linvel = linvel * towards.speed * SPEED_FRAME;
         body.position.rotation =
             bevy_rapier2d::na::UnitComplex::from_angle(rot - std::f32::consts::FRAC_PI_2);
     }
@@ -453,11 +465,13 @@ pub fn ship_collision(
                             game_events.send(crate::game::GameEvents::ShipDamaged(
                                 entity1,
                                 ship2.hit_points,
+                                Some(owner2.clone()),
                             ));
                             removed.insert(entity1);
                             game_events.send(crate::game::GameEvents::ShipDamaged(
                                 entity2,
                                 ship1.hit_points,
+                                Some(owner1.clone()),
                             ));
                             removed.insert(entity2);
                         }
@@ -477,52 +491,55 @@ pub fn game_events(
         Local<EventReader<crate::game::GameEvents>>,
         Res<Events<crate::game::GameEvents>>,
     ),
-    asset_handles: ResMut<crate::AssetHandles>,
-    mut ship_info: Query<(&mut Ship, &GlobalTransform), With<crate::space::Ship>>,
+    (mut explosions, mut interesting_events): (
+        ResMut<Events<ShipExploded>>,
+        ResMut<Events<crate::game::InterestingEvent>>,
+    ),
+    mut ship_info: Query<(&mut Ship, &GlobalTransform, &crate::game::OwnedBy)>,
     mut query_moon: Query<(&crate::space::SpawnShip, &mut crate::game::OwnedBy)>,
     mut query_ships: Query<
         (&mut crate::space::Orbiter, &crate::game::OwnedBy),
         With<crate::space::Ship>,
     >,
 ) {
+    // a ship can be hit several times before it is despawned
+    let mut destroyed = std::collections::HashSet::new();
     for event in event_reader.iter(&events) {
         match event {
-            crate::game::GameEvents::ShipDamaged(entity, damage) => {
-                if let Ok((mut ship, gt)) = ship_info.get_mut(*entity) {
+            crate::game::GameEvents::ShipDamaged(entity, damage, by) => {
+                if let Ok((mut ship, gt, owner)) = ship_info.get_mut(*entity) {
                     ship.hit_points -= damage;
-                    if ship.hit_points <= 0 {
+                    if ship.hit_points <= 0 && destroyed.insert(*entity) {
                         commands.despawn_recursive(*entity);
-                        let explosion_handle =
-                            asset_handles.get_game_handles_unsafe().explosion_handle;
-                        commands
-                            .spawn(SpriteSheetBundle {
-                                sprite: TextureAtlasSprite {
-                                    index: 0,
-                                    ..Default::default()
-                                },
-                                texture_atlas: explosion_handle.clone(),
-                                transform: (*gt).into(),
-                                ..Default::default()
-                            })
-                            .with(Explosion {
-                                timer: Timer::from_seconds(0.1, true),
-                            });
+                        explosions.send(ShipExploded(*gt));
+                        interesting_events.send(crate::game::InterestingEvent::ShipDestroyed {
+                            owner: owner.clone(),
+                            by: by.clone(),
+                        });
                     }
                 }
             }
             crate::game::GameEvents::MoonConquered(entity, new_owner) => {
                 if let Ok((spawnship, mut owner)) = query_moon.get_mut(*entity) {
-                    if let crate::game::OwnedBy::Player(0) = *new_owner {
-                        commands.insert_one(
-                            *entity,
-                            SpawnShipType::Basic.to_components(spawnship.rotation_direction),
-                        );
+                    if let crate::game::OwnedBy::Player(_) = *new_owner {
+                        let kind = match spawnship.kind {
+                            SpawnShipType::Neutral => SpawnShipType::Basic,
+                            kind => kind,
+                        };
+                        commands
+                            .insert_one(*entity, kind.to_components(spawnship.rotation_direction));
                     } else {
                         commands.insert_one(
                             *entity,
                             SpawnShipType::Neutral.to_components(spawnship.rotation_direction),
                         );
                     }
+                    if *owner != *new_owner {
+                        interesting_events.send(crate::game::InterestingEvent::MoonCaptured {
+                            from: owner.clone(),
+                            to: new_owner.clone(),
+                        });
+                    }
                     *owner = new_owner.clone();
                     query_ships
                         .iter_mut()
@@ -555,11 +572,11 @@ pub struct Shield(Timer);
 
 pub fn manage_shield(
     commands: &mut Commands,
-    time: Res<Time>,
+    clock: Res<crate::clock::GameClock>,
     mut query_shields: Query<(Entity, &mut Shield)>,
 ) {
     for (entity, mut shield) in query_shields.iter_mut() {
-        shield.0.tick(time.delta_seconds());
+        shield.0.tick(clock.delta_seconds());
         if shield.0.just_finished() {
             commands.despawn_recursive(entity);
         }
@@ -567,16 +584,22 @@ pub fn manage_shield(
 }
 
 pub fn object_collision(
+    commands: &mut Commands,
     game: Res<crate::game::Game>,
     events: Res<bevy_rapier2d::physics::EventQueue>,
     bodies: Res<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
     colliders: Res<bevy_rapier2d::rapier::geometry::ColliderSet>,
-    mut game_events: ResMut<Events<crate::game::GameEvents>>,
+    (mut game_events, mut explosions): (
+        ResMut<Events<crate::game::GameEvents>>,
+        ResMut<Events<ShipExploded>>,
+    ),
     ship_owner: Query<&crate::game::OwnedBy, With<crate::space::Ship>>,
+    missiles: Query<(&crate::game::missile::Missile, &GlobalTransform)>,
     planet_owner: Query<&crate::game::OwnedBy, With<crate::game::Planet>>,
     asteroid: Query<&crate::game::Asteroid>,
     shielded: Query<&Parent, With<Shielded>>,
 ) {
+    let mut exploded = vec![];
     while let Ok(event) = events.proximity_events.pop() {
         let entity1 = Entity::from_bits(
             bodies
@@ -591,6 +614,28 @@ pub fn object_collision(
                 .user_data as u64,
         );
         if let bevy_rapier2d::rapier::ncollide::query::Proximity::Intersecting = event.new_status {
+            let missile_hit = match (missiles.get(entity1), missiles.get(entity2)) {
+                (Ok(missile), _) => Some((entity1, missile, entity2)),
+                (_, Ok(missile)) => Some((entity2, missile, entity1)),
+                _ => None,
+            };
+            if let Some((missile_entity, (missile, gt), ship)) = missile_hit {
+                // missiles go through everything but the ships of the other sides
+                match ship_owner.get(ship) {
+                    Ok(owner) if *owner != missile.owner && !exploded.contains(&missile_entity) => {
+                        game_events.send(crate::game::GameEvents::ShipDamaged(
+                            ship,
+                            missile.damage,
+                            Some(missile.owner.clone()),
+                        ));
+                        commands.despawn_recursive(missile_entity);
+                        explosions.send(ShipExploded(*gt));
+                        exploded.push(missile_entity);
+                    }
+                    _ => (),
+                }
+                continue;
+            }
             let (ship, asteroid) =
                 match (asteroid.get(entity1).is_ok(), asteroid.get(entity2).is_ok()) {
                     (true, _) => (entity2, true),
@@ -598,7 +643,7 @@ pub fn object_collision(
                     _ => (entity1, false),
                 };
             if asteroid {
-                game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500));
+                game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500, None));
             }
             let (ship, planet) = match (
                 planet_owner.get(entity1).is_ok(),
@@ -610,10 +655,10 @@ pub fn object_collision(
             };
             if let Ok(crate::game::OwnedBy::Player(0)) = ship_owner.get(ship) {
                 if game.neutral_moons != 0 {
-                    game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500));
+                    game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500, None));
                     game_events.send(crate::game::GameEvents::PlanetShield(planet, 0.5));
                 } else if shielded.iter().find(|parent| parent.0 == planet).is_some() {
-                    game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500));
+                    game_events.send(crate::game::GameEvents::ShipDamaged(ship, 500, None));
                 } else {
                     game_events.send(crate::game::GameEvents::PlanetConquered(planet));
                 }
@@ -622,88 +667,34 @@ pub fn object_collision(
     }
 }
 
-struct Explosion {
-    timer: Timer,
-}
-fn explode(
-    commands: &mut Commands,
-    time: Res<Time>,
-    mut query: Query<(&mut Explosion, &mut TextureAtlasSprite, Entity)>,
-) {
-    for (mut explosion, mut atlas_sprite, entity) in query.iter_mut() {
-        explosion.timer.tick(time.delta_seconds());
-        if explosion.timer.just_finished() {
-            match atlas_sprite.index {
-                6 => {
-                    commands.despawn_recursive(entity);
-                }
-                n => atlas_sprite.index = n + 1,
-            }
-        }
-    }
-}
-
 pub struct Shielded {
     pub timer: Timer,
 }
 
 fn shielded(
     commands: &mut Commands,
-    time: Res<Time>,
-    mut asset_handles: ResMut<crate::AssetHandles>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    bodies: Res<bevy_rapier2d::rapier::dynamics::RigidBodySet>,
-    mut shieldeds: Query<(Entity, &Parent, &mut Shielded)>,
-    query: Query<(
-        &crate::game::ui::InteractionBox,
-        Option<&bevy_rapier2d::physics::RigidBodyHandleComponent>,
-    )>,
+    clock: Res<crate::clock::GameClock>,
+    game: Res<crate::game::Game>,
+    mut interesting_events: ResMut<Events<crate::game::InterestingEvent>>,
+    mut shieldeds: Query<(Entity, &mut Shielded, &Parent)>,
 ) {
-    for (shield_entity, planet_entity, mut shielded) in shieldeds.iter_mut() {
-        shielded.timer.tick(time.delta_seconds());
-
-        let (interaction_box, rigid_body) = query.get(planet_entity.0).unwrap();
-
-        if let Some(rigid_body) = rigid_body {
-            let color_spawn_progress = asset_handles.get_color_spawning_enemy(&mut materials);
-            let body = bodies.get(rigid_body.handle()).unwrap();
-
-            let angle = (shielded.timer.duration() - shielded.timer.elapsed())
-                / shielded.timer.duration()
-                * 2.
-                * std::f32::consts::PI;
-
-            let radius = interaction_box.radius * 10. - 20.;
-            let start_x =
-                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).cos() * radius;
-            let start_y =
-                (-body.position.rotation.angle() + std::f32::consts::FRAC_PI_2).sin() * radius;
-            let mut builder = bevy_prototype_lyon::path::PathBuilder::new();
-            builder.move_to(bevy_prototype_lyon::prelude::point(start_x, start_y));
-            builder.arc(
-                bevy_prototype_lyon::prelude::point(0., 0.),
-                radius,
-                radius,
-                angle,
-                0.,
-            );
-            let path = builder.build();
-            let sprite = path.stroke(
-                color_spawn_progress.clone(),
-                &mut meshes,
-                Vec3::new(0.0, 0.0, 0.0),
-                &bevy_prototype_lyon::prelude::StrokeOptions::default()
-                    .with_line_width(20.)
-                    .with_line_cap(bevy_prototype_lyon::prelude::LineCap::Round)
-                    .with_line_join(bevy_prototype_lyon::prelude::LineJoin::Round),
-            );
-
-            commands.insert(shield_entity, sprite);
-        }
-
+    let mut dropped = vec![];
+    for (shield_entity, mut shielded, parent) in shieldeds.iter_mut() {
+        shielded.timer.tick(clock.delta_seconds());
         if shielded.timer.just_finished() {
             commands.despawn_recursive(shield_entity);
+            dropped.push(parent.0);
+        }
+    }
+    // the planet is open once all moons are taken and its last shield is gone
+    if game.neutral_moons == 0 {
+        for planet in dropped {
+            if !shieldeds
+                .iter_mut()
+                .any(|(_, shielded, parent)| parent.0 == planet && !shielded.timer.finished())
+            {
+                interesting_events.send(crate::game::InterestingEvent::ShieldDropped);
+            }
         }
     }
 }
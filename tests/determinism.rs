@@ -0,0 +1,199 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use wotm::game::{Game, Moon, Order, OwnedBy, Planet, Ratio};
+
+const SEED: u64 = 42;
+const FRAMES: usize = 900;
+// frames between two orders of the player
+const ORDER_EVERY: usize = 150;
+
+// How a match was set up, to be compared with another app, where entities differ.
+#[derive(Debug, PartialEq)]
+struct Layout {
+    planet: Vec<(String, usize)>,
+    // by moon index
+    moons: Vec<(i32, OwnedBy, f32, f32)>,
+}
+
+fn layout(app: &App) -> Layout {
+    let planet = app
+        .world
+        .query::<&Planet>()
+        .map(|planet| (planet.name.clone(), planet.kind))
+        .collect();
+    let mut moons = app
+        .world
+        .query::<(&Moon, &OwnedBy, &wotm::space::Orbiter)>()
+        .map(|(moon, owner, orbiter)| (moon.index, owner.clone(), orbiter.distance, orbiter.speed))
+        .collect::<Vec<_>>();
+    moons.sort_by_key(|(index, _, _, _)| *index);
+    Layout { planet, moons }
+}
+
+fn set_up_match(seed: u64) -> Layout {
+    let result = Arc::new(Mutex::new(None));
+    let runner_result = result.clone();
+    App::build()
+        .add_resource(wotm::GameScreen {
+            current_screen: wotm::Screen::Game,
+            ..Default::default()
+        })
+        .add_resource(wotm::rng::MatchRng::with_seed(Some(seed)))
+        .add_plugins(MinimalPlugins)
+        .add_plugins(wotm::HeadlessPlugins)
+        .set_runner(move |mut app| {
+            for _ in 0..2 {
+                app.update();
+            }
+            *runner_result.lock().unwrap() = Some(layout(&app));
+        })
+        .run();
+    let layout = result.lock().unwrap().take();
+    layout.unwrap()
+}
+
+// The state of a match that can be compared with another app, where entities differ.
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    // by moon index
+    owners: Vec<(i32, OwnedBy)>,
+    ships: Vec<(i32, String, usize)>,
+}
+
+fn outcome(app: &App) -> Outcome {
+    let game = app.resources.get::<Game>().unwrap();
+    let mut owners = vec![];
+    let mut ships = vec![];
+    for (entity, moon, owner) in app.world.query::<(Entity, &Moon, &OwnedBy)>() {
+        owners.push((moon.index, owner.clone()));
+        for (owner, count) in game.ship_counts.get(&entity).into_iter().flatten() {
+            ships.push((moon.index, format!("{:?}", owner), *count));
+        }
+    }
+    owners.sort_by_key(|(index, _)| *index);
+    ships.sort();
+    Outcome { owners, ships }
+}
+
+// the player sends half the ships of their first moon at another moon, a different one each time
+fn send_orders(app: &mut App, frame: usize) {
+    if frame == 0 || frame % ORDER_EVERY != 0 {
+        return;
+    }
+    let mut moons = app
+        .world
+        .query::<(Entity, &Moon, &OwnedBy)>()
+        .map(|(entity, moon, owner)| (moon.index, entity, owner.clone()))
+        .collect::<Vec<_>>();
+    moons.sort_by_key(|(index, _, _)| *index);
+    let from = match moons
+        .iter()
+        .find(|(_, _, owner)| *owner == OwnedBy::Player(0))
+    {
+        Some((_, from, _)) => *from,
+        None => return,
+    };
+    let others = moons
+        .iter()
+        .filter(|(_, entity, _)| *entity != from)
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        return;
+    }
+    let towards = others[(frame / ORDER_EVERY) % others.len()].1;
+    app.resources
+        .get_mut::<Events<Order>>()
+        .unwrap()
+        .send(Order::SendShips {
+            owner: OwnedBy::Player(0),
+            from,
+            towards,
+            ratio: Ratio::Half,
+        });
+}
+
+fn seeded() -> AppBuilder {
+    let mut builder = App::build();
+    builder
+        .add_resource(wotm::GameScreen {
+            current_screen: wotm::Screen::Game,
+            ..Default::default()
+        })
+        .add_resource(wotm::rng::MatchRng::with_seed(Some(SEED)))
+        .add_resource(wotm::clock::GameClock::scripted(vec![1. / 60.; FRAMES]));
+    builder
+}
+
+// Plays `FRAMES` frames of the match, with the orders of the player when `orders` is set. The
+// replay recorded is saved to `save_replay`.
+fn run_match(mut builder: AppBuilder, orders: bool, save_replay: Option<String>) -> Outcome {
+    let result = Arc::new(Mutex::new(None));
+    let runner_result = result.clone();
+    builder
+        .add_plugins(MinimalPlugins)
+        .add_plugins(wotm::HeadlessPlugins)
+        .set_runner(move |mut app| {
+            for frame in 0..FRAMES {
+                if orders {
+                    send_orders(&mut app, frame);
+                }
+                app.update();
+            }
+            if let Some(path) = &save_replay {
+                let state = app.resources.get::<wotm::replay::ReplayState>().unwrap();
+                state.replay().save(path).unwrap();
+            }
+            *runner_result.lock().unwrap() = Some(outcome(&app));
+        })
+        .run();
+    let outcome = result.lock().unwrap().take();
+    outcome.unwrap()
+}
+
+#[test]
+fn same_seed_gives_the_same_match() {
+    let first = set_up_match(SEED);
+    assert!(!first.moons.is_empty());
+    assert_eq!(first, set_up_match(SEED));
+    assert_ne!(first, set_up_match(SEED + 1));
+}
+
+#[test]
+fn same_seed_and_orders_give_the_same_match() {
+    let first = run_match(seeded(), true, None);
+    let second = run_match(seeded(), true, None);
+    assert!(!first.ships.is_empty());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn saved_replay_plays_the_same_match() {
+    let path = std::env::temp_dir()
+        .join(format!("wotm-test-{}.replay", std::process::id()))
+        .to_string_lossy()
+        .to_string();
+    let recorded = run_match(seeded(), true, Some(path.clone()));
+    let replay = wotm::replay::Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut builder = App::build();
+    builder
+        .add_resource(wotm::rng::MatchRng::with_seed(Some(replay.seed)))
+        .add_resource(replay.config.clone())
+        .add_resource(replay.map.clone())
+        .add_resource(wotm::clock::GameClock::scripted(replay.deltas.clone()))
+        .add_resource(wotm::GameScreen {
+            current_screen: wotm::Screen::Game,
+            ..Default::default()
+        })
+        .add_resource(wotm::replay::ReplayState::playback(
+            replay,
+            wotm::replay::BeforeReplay {
+                fixed_seed: None,
+                config: Default::default(),
+                map: Default::default(),
+            },
+        ));
+    assert_eq!(run_match(builder, false, None), recorded);
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+const FRAMES: usize = 600;
+
+#[test]
+fn match_runs_without_a_window() {
+    App::build()
+        .add_resource(wotm::GameScreen {
+            current_screen: wotm::Screen::Game,
+            ..Default::default()
+        })
+        .add_resource(wotm::clock::GameClock::scripted(vec![1. / 60.; FRAMES]))
+        .add_plugins(MinimalPlugins)
+        .add_plugins(wotm::HeadlessPlugins)
+        .set_runner(|mut app| {
+            for _ in 0..FRAMES {
+                app.update();
+            }
+            assert_eq!(app.world.query::<&wotm::game::Planet>().count(), 1);
+            assert!(app.world.query::<&wotm::game::Moon>().count() > 0);
+            assert!(app.world.query::<&wotm::space::Ship>().count() > 0);
+        })
+        .run();
+}